@@ -9,7 +9,7 @@ fn list() {
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("list", |b| b.iter(|| list()));
+    c.bench_function("list", |b| b.iter(list));
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -1,26 +1,158 @@
 //! # Interact and opionally edit the todo.txt file.
 pub mod add;
+pub mod archive;
+pub mod deduplicate;
 pub mod delete;
+pub mod done;
+pub mod edit;
+pub mod export;
+pub mod import;
 pub mod list;
+pub mod mv;
+pub mod priority;
+pub mod report;
+pub mod tags;
 
 use crate::{
-    actions::{add::add, list::list},
-    app::Commands,
+    actions::{
+        add::add,
+        archive::archive,
+        list::{list, list_file, listpri},
+        tags::TagKind,
+    },
+    app::{BackupCommand, Commands, ListFormat},
+    backup,
     config::AppContext,
-    file::{get_done, get_tasks, write_buf_to_file},
+    file::{get_done, get_tasks, resolve_in_todo_dir, write_buf_to_file, FileLock},
+    journal::{self, Journal, Snapshot},
     prelude::*,
     task::tasks_to_string,
 };
+use std::{path::PathBuf, time::Duration};
+
+/// Seconds to wait for todo.txt lock if not set in config
+const DEFAULT_LOCK_TIMEOUT: u64 = 10;
 
 /// Direct the execution of the program based on the Command in the
-/// Context object
+/// Context object. For commands that change files, todo.txt is locked from
+/// the time it is loaded until the command has finished writing.
 pub fn handle_command<W>(ctx: &mut AppContext, buf: &mut W) -> Result
 where
     W: std::io::Write + termcolor::WriteColor,
 {
     ctx.expand_paths()?;
+    let timeout = ctx.settings.lock_timeout.unwrap_or(DEFAULT_LOCK_TIMEOUT);
+    let lock = if is_read_only(ctx) {
+        None
+    } else {
+        Some(FileLock::acquire(&ctx.todo_file, Duration::from_secs(timeout))?)
+    };
     get_tasks(ctx)?;
+    // only commands holding the lock can change files
+    let journaled = lock.is_some()
+        && !matches!(
+            ctx.opts.cmd,
+            Some(Commands::Undo { .. }) | Some(Commands::Redo { .. })
+        );
+    let snapshot = if journaled {
+        Some(Snapshot::take(&journal_files(ctx)))
+    } else {
+        None
+    };
+    backup::enable(ctx);
+    let success = run_command(ctx, buf)?;
+    if let (Some(snapshot), Some(cmd)) = (snapshot, &ctx.opts.cmd) {
+        Journal::record(ctx, &snapshot, command_text(cmd))?;
+    }
+    drop(lock);
+    if !success {
+        std::process::exit(1)
+    }
+    Ok(())
+}
+
+/// True if the Command only reads files, so it doesn't need the lock
+fn is_read_only(ctx: &AppContext) -> bool {
+    match &ctx.opts.cmd {
+        Some(cmd) => matches!(
+            cmd,
+            Commands::Backup { cmd: BackupCommand::List }
+                | Commands::Complete { .. }
+                | Commands::Export { .. }
+                | Commands::Import { dry_run: true, .. }
+                | Commands::List { .. }
+                | Commands::Listall { .. }
+                | Commands::Listcon { .. }
+                | Commands::Listfile { .. }
+                | Commands::Listpri { .. }
+                | Commands::Listproj { .. }
+                | Commands::Report { show: true }
+                | Commands::Undo { list: true, .. }
+        ),
+        None => true,
+    }
+}
+
+/// Command line of a changing Command as shown by `undo --list`
+fn command_text(cmd: &Commands) -> String {
+    let items = |items: &[usize]| {
+        let items = items.iter().map(usize::to_string).collect::<Vec<_>>();
+        items.join(",")
+    };
+    match cmd {
+        Commands::Add { task } => format!("add {:?}", task),
+        Commands::Addm { tasks } => format!("addm {:?}", tasks.join("\n")),
+        Commands::Addto { dest, task } => format!("addto {} {:?}", dest, task),
+        Commands::Append { item, text } => format!("append {} {:?}", item, text),
+        Commands::Archive => String::from("archive"),
+        Commands::Backup {
+            cmd: BackupCommand::Restore { number },
+        } => format!("backup restore {}", number),
+        Commands::Deduplicate { normalize: true } => String::from("deduplicate --normalize"),
+        Commands::Deduplicate { normalize: false } => String::from("deduplicate"),
+        Commands::Del { item, term: Some(term) } => format!("del {} {:?}", item, term),
+        Commands::Del { item, term: None } => format!("del {}", item),
+        Commands::Depri { items: i } => format!("depri {}", items(i)),
+        Commands::Do { items: i } => format!("do {}", items(i)),
+        Commands::Import { file, .. } => format!("import {}", file.display()),
+        Commands::Move { item, dest, src } => match src {
+            Some(src) => format!("move {} {} {}", item, dest, src),
+            None => format!("move {} {}", item, dest),
+        },
+        Commands::Pri { item, priority } => format!("pri {} {}", item, priority),
+        Commands::Prepend { item, text } => format!("prepend {} {:?}", item, text),
+        Commands::Replace { item, text } => format!("replace {} {:?}", item, text),
+        Commands::Report { .. } => String::from("report"),
+        cmd => format!("{:?}", cmd),
+    }
+}
+
+/// Files that may be changed by the Command and should be journaled
+fn journal_files(ctx: &AppContext) -> Vec<PathBuf> {
+    let mut files = vec![ctx.todo_file.clone(), ctx.done_file.clone()];
+    let extra = match &ctx.opts.cmd {
+        Some(Commands::Addto { dest, .. }) => vec![dest.as_str()],
+        Some(Commands::Move { dest, src, .. }) => {
+            src.iter().map(String::as_str).chain([dest.as_str()]).collect()
+        }
+        _ => Vec::new(),
+    };
+    files.extend(
+        extra
+            .into_iter()
+            .filter_map(|f| resolve_in_todo_dir(ctx, f).ok())
+            .filter(|f| !files.contains(f))
+            .collect::<Vec<PathBuf>>(),
+    );
+    files
+}
 
+/// Run the Command in the Context object. Returns false if the command
+/// could not be completed.
+fn run_command<W>(ctx: &mut AppContext, buf: &mut W) -> Result<bool>
+where
+    W: std::io::Write + termcolor::WriteColor,
+{
     // Debug print of all settings
     debug!("{:#?}", ctx.opts);
     debug!("{:#?}", ctx.settings);
@@ -42,44 +174,135 @@ where
                     write_buf_to_file(new.raw, &ctx.todo_file, true)?;
                 }
             }
-            Commands::Addto => todo!(),
+            Commands::Addto { dest, task } => {
+                if !add::addto(&dest, &task, ctx)? {
+                    return Ok(false);
+                }
+            }
             Commands::Append { item, text } => {
-                eprintln!("Appending: {:?} to task {}", text, item);
-                todo!()
+                if edit::append(item, &text, ctx)? {
+                    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                    return Ok(true);
+                }
+                return Ok(false);
             }
-            Commands::Archive => todo!(),
+            Commands::Archive => archive(ctx)?,
+            Commands::Backup { cmd } => match cmd {
+                BackupCommand::List => backup::list(buf, ctx)?,
+                BackupCommand::Restore { number } => return backup::restore(number, ctx),
+            },
             Commands::Complete { shell } => shell.generate(),
-            Commands::Deduplicate => todo!(),
+            Commands::Deduplicate { normalize } => {
+                deduplicate::deduplicate(normalize, ctx)?;
+                write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+            }
             Commands::Depri { items } => {
-                eprintln!("Deprioritizing item(s): {:?}", items);
-                todo!()
+                let success = priority::depri(&items, ctx)?;
+                write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                if !success {
+                    return Ok(false);
+                }
             }
             Commands::Del { item, term } => {
                 if delete::delete(item, &term, ctx)? {
                     write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
-                    return Ok(());
+                    return Ok(true);
+                }
+                return Ok(false);
+            }
+            Commands::Do { items } => {
+                let success = done::done(&items, ctx)?;
+                write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                if !success {
+                    return Ok(false);
+                }
+                if ctx.settings.auto_archive.unwrap_or(true) {
+                    archive(ctx)?;
+                }
+            }
+            Commands::Export { terms, format, columns, all, events } => {
+                let columns = columns.as_deref();
+                export::export(&terms, format, columns, all, events, buf, ctx)?;
+            }
+            Commands::Import { file, format, dry_run } => {
+                if !import::import(&file, format, dry_run, ctx)? {
+                    return Ok(false);
+                }
+                if !dry_run {
+                    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
                 }
-                std::process::exit(1)
             }
-            Commands::List { terms } => {
-                list(&terms, buf, ctx, false)?;
+            Commands::List { terms, format, group_by } => {
+                list(&terms, buf, ctx, false, format, group_by)?;
             }
-            Commands::Listall { terms } => {
+            Commands::Listall { terms, format, group_by } => {
                 get_done(ctx)?;
-                list(&terms, buf, ctx, true)?;
+                list(&terms, buf, ctx, true, format, group_by)?;
+            }
+            Commands::Move { item, dest, src } => {
+                if !mv::mv(item, &dest, src.as_deref(), ctx)? {
+                    return Ok(false);
+                }
+            }
+            Commands::Pri { item, priority } => {
+                if priority::pri(item, &priority, ctx)? {
+                    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                    return Ok(true);
+                }
+                return Ok(false);
+            }
+            Commands::Prepend { item, text } => {
+                if edit::prepend(item, &text, ctx)? {
+                    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                    return Ok(true);
+                }
+                return Ok(false);
+            }
+            Commands::Report { show } => {
+                if show {
+                    report::show_report(buf, ctx)?;
+                } else {
+                    report::report(ctx)?;
+                }
+            }
+            Commands::Redo { steps } => return journal::redo(steps, ctx),
+            Commands::Replace { item, text } => {
+                if edit::replace(item, &text, ctx)? {
+                    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+                    return Ok(true);
+                }
+                return Ok(false);
+            }
+            Commands::Listfile { src, terms } => {
+                list_file(src.as_deref(), &terms, buf, ctx)?;
+            }
+            Commands::Listpri { terms, format } => {
+                listpri(&terms, buf, ctx, format)?;
+            }
+            Commands::Undo { steps, list } => {
+                if list {
+                    journal::list(buf, ctx)?;
+                } else {
+                    return journal::undo(steps, ctx);
+                }
+            }
+            Commands::Listproj { terms, all, count, format } => {
+                tags::list_tags(TagKind::Project, &terms, all, count, format, buf, ctx)?;
+            }
+            Commands::Listcon { terms, all, count, format } => {
+                tags::list_tags(TagKind::Context, &terms, all, count, format, buf, ctx)?;
             }
-            Commands::Listpri { priorities } => info!("Listing priorities {:?}", priorities),
         },
         None => match &ctx.settings.default_action {
             Some(cmd) => match cmd.as_str() {
-                "ls" | "list" => list(&[], buf, ctx, false)?,
+                "ls" | "list" => list(&[], buf, ctx, false, ListFormat::Text, None)?,
                 _ => bail!("Unknown command: {:?}", cmd),
             },
             None => {
                 info!("No command supplied; defaulting to List");
-                list(&[], buf, ctx, false)?;
+                list(&[], buf, ctx, false, ListFormat::Text, None)?;
             }
         },
     }
-    Ok(())
+    Ok(true)
 }
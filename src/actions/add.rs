@@ -1,12 +1,18 @@
 //! # Add tasks to todo.txt file
-use crate::{config::AppContext, prelude::*, task::Task};
+use crate::{
+    config::AppContext,
+    file::{get_prefix, read_file_to_string, resolve_in_todo_dir, write_buf_to_file},
+    prelude::*,
+    task::Task,
+};
 use chrono::Utc;
+use regex::Regex;
 use std::io::{self, Write};
 
 /// Create task from raw input. Print confirmation and return to caller.
 pub fn add(task: String, ctx: &mut AppContext) -> Result<Task> {
     let mut task = task;
-    if task == "" {
+    if task.is_empty() {
         io::stdout().write_all(b"Add: ").unwrap();
         io::stdout().flush().unwrap();
         io::stdin().read_line(&mut task).unwrap();
@@ -21,3 +27,24 @@ pub fn add(task: String, ctx: &mut AppContext) -> Result<Task> {
     println!("TODO: {} added.", new.id);
     Ok(new)
 }
+
+/// Append task to a file in the todo.txt directory. The file must exist.
+pub fn addto(dest: &str, task: &str, ctx: &mut AppContext) -> Result<bool> {
+    let path = resolve_in_todo_dir(ctx, dest)?;
+    if !path.is_file() {
+        println!("TODO: Destination file {} does not exist.", path.display());
+        return Ok(false);
+    }
+    let mut task = task.to_string();
+    if ctx.opts.date_on_add {
+        let dt = Utc::today().format("%Y-%m-%d");
+        let re = Regex::new(r"^(\([A-Z]\) )?")?;
+        task = re.replace(&task, format!("${{1}}{} ", dt)).into_owned();
+    }
+    let line_ct = read_file_to_string(&path)?.lines().count() + 1;
+    write_buf_to_file(task.as_str(), &path, true)?;
+    let new = Task::new(line_ct, &task);
+    println!("{}", new);
+    println!("{}: {} added.", get_prefix(&path), new.id);
+    Ok(true)
+}
@@ -0,0 +1,34 @@
+//! # Move completed tasks from todo.txt to done.txt
+use crate::{config::AppContext, file::write_buf_to_file, prelude::*, task::tasks_to_string};
+
+/// Move all finished tasks to done.txt and compact todo.txt.
+///
+/// Blank lines are removed from todo.txt unless line numbers are being
+/// preserved (`-N`), in which case the archived tasks are left as blank lines.
+pub fn archive(ctx: &mut AppContext) -> Result {
+    let archived = ctx
+        .tasks
+        .iter()
+        .filter(|t| t.parsed.finished)
+        .map(|t| t.raw.clone())
+        .collect::<Vec<String>>();
+    for line in &archived {
+        println!("{}", line);
+    }
+    if ctx.opts.preserve_line_numbers {
+        for task in ctx.tasks.iter_mut() {
+            if task.parsed.finished {
+                *task = task.clear();
+            }
+        }
+    } else {
+        ctx.tasks.retain(|t| !t.parsed.finished && !t.is_blank());
+    }
+    if !archived.is_empty() {
+        info!("Archiving {} tasks to {:?}", archived.len(), ctx.done_file);
+        write_buf_to_file(archived.join("\n"), &ctx.done_file, true)?;
+    }
+    write_buf_to_file(tasks_to_string(ctx)?, &ctx.todo_file, false)?;
+    println!("TODO: {} archived.", ctx.todo_file.display());
+    Ok(())
+}
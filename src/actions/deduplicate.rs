@@ -0,0 +1,34 @@
+//! # Remove duplicate tasks from todo.txt
+use crate::{config::AppContext, prelude::*};
+use std::collections::HashSet;
+
+/// Remove duplicate tasks, keeping the first occurrence. Duplicates are
+/// cleared to blank lines, which are dropped on write if `-n` is given.
+///
+/// If `normalize` is true, tasks are compared after condensing whitespace
+/// and ignoring case.
+pub fn deduplicate(normalize: bool, ctx: &mut AppContext) -> Result {
+    let mut seen = HashSet::new();
+    let mut dupes = 0;
+    for task in ctx.tasks.iter_mut() {
+        if task.is_blank() {
+            continue;
+        }
+        let key = if normalize {
+            task.normalize_whitespace().raw.to_lowercase()
+        } else {
+            task.raw.clone()
+        };
+        if !seen.insert(key) {
+            debug!("Removing duplicate task: {}", task);
+            *task = task.clear();
+            dupes += 1;
+        }
+    }
+    if dupes == 0 {
+        println!("TODO: No duplicate tasks found");
+    } else {
+        println!("TODO: {} duplicate task(s) removed", dupes);
+    }
+    Ok(())
+}
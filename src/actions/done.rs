@@ -0,0 +1,151 @@
+//! # Mark tasks as done
+use crate::{
+    config::AppContext,
+    date::{parse_offset, shift, today},
+    prelude::*,
+    task::Task,
+};
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// Mark tasks on lines `items` as done with today's date as the completion
+/// date. Stops at the first line that doesn't exist and returns false.
+///
+/// Tasks with a `rec:` tag are followed by a new task for the next occurrence.
+pub fn done(items: &[usize], ctx: &mut AppContext) -> Result<bool> {
+    let today = today();
+    let keep_pri = ctx.settings.preserve_priority.unwrap_or(false);
+    for &item in items {
+        let task = match ctx.tasks.get_by_id_mut(item) {
+            Some(t) => t,
+            None => {
+                println!("TODO: No task {}.", item);
+                return Ok(false);
+            }
+        };
+        if task.parsed.finished {
+            println!("TODO: {} is already marked done.", item);
+            continue;
+        }
+        let original = task.clone();
+        let text = match task.priority_prefix() {
+            Some(p) if keep_pri => format!("{} pri:{}", task.with_priority(None).raw, p),
+            _ => task.with_priority(None).raw,
+        };
+        *task = Task::new(item, &format!("x {} {}", today.format("%Y-%m-%d"), text));
+        info!("Task after completing: {}", task.raw);
+        println!("{}", task);
+        println!("TODO: {} marked as done.", item);
+        if let Some(rec) = original.parsed.tags.get("rec") {
+            match next_occurrence(&original.raw, rec, &original.parsed, today) {
+                Some(raw) => {
+                    ctx.task_ct += 1;
+                    let new = Task::new(ctx.task_ct, &raw);
+                    println!("{}", new);
+                    println!("TODO: {} added.", new.id);
+                    ctx.tasks.push(new);
+                }
+                None => println!("TODO: Invalid recurrence 'rec:{}'; no task added.", rec),
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Raw text of the next occurrence of a recurring task.
+///
+/// The due and threshold dates are moved by the `rec:` interval, e.g. `1w` or
+/// `3m`. Normally the new due date is counted from `today`, keeping the same
+/// gap to the threshold date; with a leading `+` (strict mode) both dates are
+/// counted from their old values. A task without either date gets a due date.
+/// A creation date is replaced with `today`.
+fn next_occurrence(
+    raw: &str,
+    rec: &str,
+    parsed: &todo_txt::Task,
+    today: NaiveDate,
+) -> Option<String> {
+    let strict = rec.starts_with('+');
+    let (n, unit) = parse_offset(rec.trim_start_matches('+'))?;
+    let next = |date: NaiveDate| shift(date, n, unit);
+    let (due, threshold) = match (parsed.due_date, parsed.threshold_date) {
+        (due, threshold) if strict && (due.is_some() || threshold.is_some()) => (
+            match due {
+                Some(d) => Some(next(d)?),
+                None => None,
+            },
+            match threshold {
+                Some(t) => Some(next(t)?),
+                None => None,
+            },
+        ),
+        (Some(due), threshold) => {
+            let new_due = next(today)?;
+            let threshold = match threshold {
+                Some(t) => Some(new_due.checked_sub_signed(due - t)?),
+                None => None,
+            };
+            (Some(new_due), threshold)
+        }
+        (None, Some(_)) => (None, Some(next(today)?)),
+        (None, None) => (Some(next(today)?), None),
+    };
+    let mut raw = raw.to_string();
+    for (key, date) in &[("due", due), ("t", threshold)] {
+        if let Some(date) = date {
+            let re = Regex::new(&format!(r"(^|\s){}:\S+", key)).unwrap();
+            let tag = format!("{}:{}", key, date.format("%Y-%m-%d"));
+            if re.is_match(&raw) {
+                raw = re.replace(&raw, format!("${{1}}{}", tag)).into_owned();
+            } else {
+                raw = format!("{} {}", raw, tag);
+            }
+        }
+    }
+    if parsed.create_date.is_some() {
+        let re = Regex::new(r"^(\(.\) )?[0-9]{4}-[0-9]{2}-[0-9]{2} ").unwrap();
+        let created = format!("${{1}}{} ", today.format("%Y-%m-%d"));
+        raw = re.replace(&raw, created.as_str()).into_owned();
+    }
+    Some(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Next occurrence of raw task completed on 2026-10-16
+    fn next(raw: &str) -> Option<String> {
+        let task = Task::new(1, raw);
+        let rec = task.parsed.tags["rec"].clone();
+        next_occurrence(raw, &rec, &task.parsed, NaiveDate::from_ymd(2026, 10, 16))
+    }
+
+    #[test]
+    /// Dates move from the completion date, or from the old dates in strict mode
+    fn recurrence_dates() {
+        assert_eq!(
+            next("(A) 2026-10-01 Pay rent +home due:2026-10-10 t:2026-10-05 rec:1m").unwrap(),
+            "(A) 2026-10-16 Pay rent +home due:2026-11-16 t:2026-11-11 rec:1m"
+        );
+        assert_eq!(
+            next("Pay rent due:2026-10-10 t:2026-10-05 rec:+1m").unwrap(),
+            "Pay rent due:2026-11-10 t:2026-11-05 rec:+1m"
+        );
+        assert_eq!(
+            next("Water plants @home rec:1w").unwrap(),
+            "Water plants @home rec:1w due:2026-10-23"
+        );
+        assert_eq!(next("Water plants rec:often"), None);
+    }
+
+    #[test]
+    /// Intervals that move dates out of range give no next occurrence
+    fn recurrence_out_of_range() {
+        assert_eq!(next("Pay rent due:2026-10-10 rec:+99999999d"), None);
+        assert_eq!(next("Pay rent due:2026-10-10 t:2026-10-05 rec:300000000y"), None);
+        assert_eq!(next("Water plants rec:-99999999w"), None);
+        assert_eq!(next("Water plants rec:99999999999d"), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! # Edit the text of existing tasks
+use crate::{config::AppContext, prelude::*, task::Task};
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Characters that don't need a space when appended to a task
+const SENTENCE_DELIMITERS: &[char] = &[',', '.', ':', ';'];
+
+/// Creation date at the start of a line, as matched by todo.sh
+fn date_prefix() -> &'static Regex {
+    static DATE_PREFIX: OnceLock<Regex> = OnceLock::new();
+    DATE_PREFIX.get_or_init(|| Regex::new(r"^[0-9]{2,4}-[0-9]{2}-[0-9]{2} ").unwrap())
+}
+
+/// Split raw line into priority prefix, creation date prefix, and the rest
+fn split_prefix(raw: &str) -> (&str, &str, &str) {
+    let pri_len = match Task::new(0, raw).priority_prefix() {
+        Some(_) => "(A) ".len(),
+        None => 0,
+    };
+    let (pri, rest) = raw.split_at(pri_len);
+    let (date, rest) = rest.split_at(date_prefix().find(rest).map_or(0, |m| m.end()));
+    (pri, date, rest)
+}
+
+/// Apply `edit` to the raw text of task `item`, normalize whitespace and
+/// print the line before and after, separated by `note` if given. Returns
+/// false if there is no such task.
+fn edit_task<F>(item: usize, ctx: &mut AppContext, note: Option<&str>, edit: F) -> Result<bool>
+where
+    F: FnOnce(&str) -> String,
+{
+    let task = match ctx.tasks.get_by_id_mut(item) {
+        Some(t) => t,
+        None => {
+            println!("TODO: No task {}.", item);
+            return Ok(false);
+        }
+    };
+    println!("{}", task);
+    if let Some(note) = note {
+        println!("{}", note);
+    }
+    let new = Task::new(item, &edit(&task.raw)).normalize_whitespace();
+    info!("Task after editing: {}", new.raw);
+    println!("{}", new);
+    *task = new;
+    Ok(true)
+}
+
+/// Add text to end of task
+pub fn append(item: usize, text: &str, ctx: &mut AppContext) -> Result<bool> {
+    edit_task(item, ctx, None, |raw| {
+        let sep = if text.starts_with(SENTENCE_DELIMITERS) { "" } else { " " };
+        format!("{}{}{}", raw, sep, text)
+    })
+}
+
+/// Add text to beginning of task, after any priority and creation date
+pub fn prepend(item: usize, text: &str, ctx: &mut AppContext) -> Result<bool> {
+    edit_task(item, ctx, None, |raw| {
+        let (pri, date, rest) = split_prefix(raw);
+        format!("{}{}{} {}", pri, date, text, rest)
+    })
+}
+
+/// Replace task text. The existing priority and creation date are kept unless
+/// the new text contains its own.
+pub fn replace(item: usize, text: &str, ctx: &mut AppContext) -> Result<bool> {
+    edit_task(item, ctx, Some("TODO: Replaced task with:"), |raw| {
+        let (old_pri, old_date, _) = split_prefix(raw);
+        let (new_pri, new_date, rest) = split_prefix(text);
+        format!(
+            "{}{}{}",
+            if new_pri.is_empty() { old_pri } else { new_pri },
+            if new_date.is_empty() { old_date } else { new_date },
+            rest
+        )
+    })
+}
@@ -0,0 +1,113 @@
+//! # Export tasks to other formats
+use crate::{
+    actions::tags::TagKind,
+    app::ExportFormat,
+    config::AppContext,
+    csv::write_record,
+    file::get_done,
+    filter::Filter,
+    ics::write_calendar,
+    markup::{write_html, write_markdown},
+    prelude::*,
+    task::{SortBy, Task},
+};
+use clap::ArgEnum;
+
+/// Columns written if not set by flag or config
+const DEFAULT_COLUMNS: &[SortBy] = &[
+    SortBy::Id,
+    SortBy::Priority,
+    SortBy::CreateDate,
+    SortBy::Body,
+    SortBy::Project,
+    SortBy::Context,
+    SortBy::DueDate,
+    SortBy::ThresholdDate,
+];
+
+/// Write tasks matching `terms` in `format`. Tasks from done.txt are
+/// included if `all` is true. Calendars contain VEVENTs if `events` is true.
+pub fn export<W>(
+    terms: &[String],
+    format: ExportFormat,
+    columns: Option<&[SortBy]>,
+    all: bool,
+    events: bool,
+    buf: &mut W,
+    ctx: &mut AppContext,
+) -> Result
+where
+    W: std::io::Write,
+{
+    let columns = match columns {
+        Some(c) => c.to_vec(),
+        None => match &ctx.settings.export_columns {
+            Some(s) => parse_columns(s)?,
+            None => DEFAULT_COLUMNS.to_vec(),
+        },
+    };
+    if all {
+        get_done(ctx)?;
+    }
+    let mut tasks = ctx.tasks.clone();
+    tasks += ctx.done.clone();
+    tasks.retain(|t| !t.is_blank());
+    tasks.filter(&Filter::parse(terms)?);
+    let delimiter = match format {
+        ExportFormat::Csv => ',',
+        ExportFormat::Tsv => '\t',
+        ExportFormat::Ics => return write_calendar(buf, &tasks, events),
+        ExportFormat::Markdown => return write_markdown(buf, &tasks),
+        ExportFormat::Html => return write_html(buf, &tasks, ctx),
+    };
+    let header = columns.iter().map(|&c| column_name(c)).collect::<Vec<String>>();
+    write_record(buf, &header, delimiter)?;
+    for task in tasks.iter() {
+        let fields = columns
+            .iter()
+            .map(|&c| field_value(task, c))
+            .collect::<Vec<String>>();
+        write_record(buf, &fields, delimiter)?;
+    }
+    Ok(())
+}
+
+/// Parse comma-separated field names, e.g. `id,priority,body`
+fn parse_columns(s: &str) -> Result<Vec<SortBy>> {
+    s.split(',')
+        .map(|c| {
+            SortBy::from_str(c.trim(), true).map_err(|_| format_err!("invalid export column: {}", c))
+        })
+        .collect()
+}
+
+/// Name of field as used by `--columns` and in the header row
+pub(crate) fn column_name(field: SortBy) -> String {
+    field
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Value of field in task. Projects and contexts are written as in the task
+/// and separated by spaces; dates as YYYY-MM-DD.
+pub(crate) fn field_value(task: &Task, field: SortBy) -> String {
+    let date = |d: Option<chrono::NaiveDate>| {
+        d.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default()
+    };
+    let p = &task.parsed;
+    match field {
+        SortBy::Body => p.subject.clone(),
+        SortBy::CompleteDate => date(p.finish_date),
+        SortBy::Completed => if p.finished { "x" } else { "" }.to_string(),
+        SortBy::Context => TagKind::Context.words(task).join(" "),
+        SortBy::CreateDate => date(p.create_date),
+        SortBy::DueDate => date(p.due_date),
+        SortBy::Id => task.id.to_string(),
+        SortBy::Priority if p.priority.is_lowest() => String::new(),
+        SortBy::Priority => char::from(p.priority.clone()).to_string(),
+        SortBy::Project => TagKind::Project.words(task).join(" "),
+        SortBy::Raw => task.raw.clone(),
+        SortBy::ThresholdDate => date(p.threshold_date),
+    }
+}
@@ -0,0 +1,212 @@
+//! # Import tasks from CSV or TSV files
+use crate::{
+    app::ExportFormat,
+    config::AppContext,
+    csv::parse_records,
+    file::read_file_to_string,
+    prelude::*,
+    task::{SortBy, Task},
+};
+use clap::ArgEnum;
+use std::path::Path;
+
+/// Column of the imported file
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    Field(SortBy),
+    Tag(String),
+}
+
+impl Column {
+    /// Column for header name, e.g. `due-date` or `estimate`
+    fn from_header(name: &str) -> Self {
+        let name = name.trim().to_lowercase().replace(' ', "_");
+        match SortBy::from_str(&name.replace('_', "-"), true) {
+            Ok(field) => Self::Field(field),
+            Err(_) => Self::Tag(name),
+        }
+    }
+}
+
+/// Add tasks from `file` to todo.txt, or only print them if `dry_run` is true
+pub fn import(
+    file: &Path,
+    format: Option<ExportFormat>,
+    dry_run: bool,
+    ctx: &mut AppContext,
+) -> Result<bool> {
+    let format = format.unwrap_or_else(|| match file.extension() {
+        Some(ext) if ext == "tsv" => ExportFormat::Tsv,
+        _ => ExportFormat::Csv,
+    });
+    let delimiter = match format {
+        ExportFormat::Csv => ',',
+        ExportFormat::Tsv => '\t',
+        _ => bail!("can't import {:?} files", format),
+    };
+    let records = parse_records(&read_file_to_string(file)?, delimiter)
+        .with_context(|| format!("reading {:?}", file))?;
+    let (header, rows) = match records.split_first() {
+        Some((header, rows)) => {
+            let header = header.iter().map(|h| Column::from_header(h)).collect::<Vec<Column>>();
+            (header, rows)
+        }
+        None => {
+            println!("TODO: No tasks in {}.", file.display());
+            return Ok(false);
+        }
+    };
+    let mut ct = 0;
+    for row in rows {
+        let line = build_line(&header, row);
+        if line.is_empty() {
+            continue;
+        }
+        ct += 1;
+        let task = Task::new(ctx.task_ct + ct, &line);
+        println!("{}", task);
+        if !dry_run {
+            ctx.tasks.push(task);
+        }
+    }
+    if dry_run {
+        println!("TODO: {} task(s) would be added (dry run).", ct);
+    } else {
+        ctx.task_ct += ct;
+        println!("TODO: {} task(s) imported.", ct);
+    }
+    Ok(true)
+}
+
+/// Build todo.txt line `x done (X) created text +proj @ctx key:val` from
+/// the values of a row. Line breaks within values become spaces. Done tasks
+/// keep their priority as a `pri:X` tag, as `do` does with `preserve_priority`.
+fn build_line(header: &[Column], row: &[String]) -> String {
+    let row = row
+        .iter()
+        .map(|v| v.replace(['\r', '\n'], " "))
+        .collect::<Vec<String>>();
+    let mut raw = None;
+    let (mut done, mut done_date, mut pri, mut created) = (false, "", None, "");
+    let mut text = String::new();
+    let mut extra = Vec::new();
+    for (col, value) in header.iter().zip(&row) {
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match col {
+            Column::Field(SortBy::Raw) => raw = Some(value),
+            Column::Field(SortBy::Body) => text = value.to_string(),
+            Column::Field(SortBy::Completed) => {
+                done = matches!(value.to_lowercase().as_str(), "x" | "1" | "true" | "yes")
+            }
+            Column::Field(SortBy::CompleteDate) => done_date = value,
+            Column::Field(SortBy::CreateDate) => created = value,
+            Column::Field(SortBy::Priority) => {
+                pri = value.chars().next().filter(char::is_ascii_alphabetic)
+            }
+            Column::Field(SortBy::Project) => extra.extend(tag_words('+', value)),
+            Column::Field(SortBy::Context) => extra.extend(tag_words('@', value)),
+            Column::Field(SortBy::DueDate) => extra.push(format!("due:{}", value)),
+            Column::Field(SortBy::ThresholdDate) => extra.push(format!("t:{}", value)),
+            Column::Field(SortBy::Id) => {}
+            Column::Tag(key) => extra.push(format!("{}:{}", key, value.replace(' ', "_"))),
+        }
+    }
+    if let Some(raw) = raw {
+        return raw.to_string();
+    }
+    if text.is_empty() && extra.is_empty() {
+        return String::new();
+    }
+    let mut words = Vec::new();
+    if done || !done_date.is_empty() {
+        words.push("x".to_string());
+        if !done_date.is_empty() {
+            words.push(done_date.to_string());
+        }
+    }
+    match pri.map(|p| p.to_ascii_uppercase()) {
+        Some(p) if words.is_empty() => words.push(format!("({})", p)),
+        Some(p) => extra.push(format!("pri:{}", p)),
+        None => {}
+    }
+    if !created.is_empty() {
+        words.push(created.to_string());
+    }
+    // projects and contexts may already be part of the body
+    let existing = text.to_lowercase().split_whitespace().map(String::from).collect::<Vec<_>>();
+    if !text.is_empty() {
+        words.push(text);
+    }
+    words.extend(
+        extra
+            .into_iter()
+            .filter(|w| !existing.contains(&w.to_lowercase())),
+    );
+    words.join(" ")
+}
+
+/// Words of a project or context column with `sigil` added if missing
+fn tag_words(sigil: char, value: &str) -> Vec<String> {
+    value
+        .split_whitespace()
+        .map(|w| format!("{}{}", sigil, w.trim_start_matches(sigil)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn line(header: &[&str], row: &[&str]) -> String {
+        let header = header.iter().map(|h| Column::from_header(h)).collect::<Vec<_>>();
+        let row = row.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        build_line(&header, &row)
+    }
+
+    #[test]
+    /// Columns are mapped back into todo.txt syntax
+    fn import_build_line() {
+        let header = [
+            "id",
+            "priority",
+            "create-date",
+            "body",
+            "project",
+            "context",
+            "due-date",
+            "Estimate",
+        ];
+        assert_eq!(
+            line(&header, &[
+                "3",
+                "a",
+                "2026-10-01",
+                "Call Mom @phone",
+                "+Family",
+                "phone errands",
+                "2026-10-20",
+                "1 h"
+            ]),
+            "(A) 2026-10-01 Call Mom @phone +Family @errands due:2026-10-20 estimate:1_h"
+        );
+        assert_eq!(
+            line(&["completed", "complete_date", "body"], &["x", "2026-10-02", "Pay rent"]),
+            "x 2026-10-02 Pay rent"
+        );
+        assert_eq!(
+            line(&["completed", "priority", "body"], &["x", "B", "Pay rent"]),
+            "x Pay rent pri:B"
+        );
+        assert_eq!(line(&["raw", "body"], &["(B) As is", "ignored"]), "(B) As is");
+        assert_eq!(line(&["raw"], &["Two\r\nlines"]), "Two  lines");
+        assert_eq!(
+            line(&["body", "project", "note"], &["Call\nMom", "Family\nHome", "a\nb"]),
+            "Call Mom +Family +Home note:a_b"
+        );
+        assert_eq!(line(&["id", "body"], &["4", ""]), "");
+    }
+}
@@ -1,10 +1,123 @@
-use crate::{config::AppContext, prelude::*, style::format_buffer, task::SortBy};
+use crate::{
+    app::{GroupBy, ListFormat},
+    config::AppContext,
+    date::today,
+    file::{get_prefix, get_tasks_from_file, resolve_in_todo_dir},
+    filter::Filter,
+    group::group_tasks,
+    json::{write_json, JsonTask, Summary},
+    prelude::*,
+    style::{format_buffer, format_tasks, get_colors_from_style},
+    task::{parse_sort_keys, Missing, SortBy, SortKey, Task},
+};
 use log::{debug, info};
+use std::fs;
 
-/// List tasks from todo.txt and done.txt files
-pub fn list<T>(terms: &[String], buf: &mut T, ctx: &mut AppContext, list_all: bool) -> Result
+/// List tasks from todo.txt and done.txt files, in sections if `group_by`
+/// is set
+pub fn list<T>(
+    terms: &[String],
+    buf: &mut T,
+    ctx: &mut AppContext,
+    list_all: bool,
+    format: ListFormat,
+    group_by: Option<GroupBy>,
+) -> Result
 where
     T: std::io::Write + termcolor::WriteColor,
+{
+    list_tasks(terms, buf, ctx, list_all, format, group_by, "TODO", |_| true)
+}
+
+/// Parse priority or priority range, e.g. `A` or `A-C`
+fn parse_priority_range(s: &str) -> Option<(char, char)> {
+    let chars = s.to_ascii_uppercase().chars().collect::<Vec<char>>();
+    match chars.as_slice() {
+        [p] if p.is_ascii_uppercase() => Some((*p, *p)),
+        [from, '-', to] if from.is_ascii_uppercase() && to.is_ascii_uppercase() && from <= to => {
+            Some((*from, *to))
+        }
+        _ => None,
+    }
+}
+
+/// List prioritized tasks from todo.txt. If the first term is a priority or
+/// priority range (e.g. `A` or `A-C`), only tasks with those priorities are
+/// shown; the remaining terms filter as in `list`.
+pub fn listpri<T>(
+    terms: &[String],
+    buf: &mut T,
+    ctx: &mut AppContext,
+    format: ListFormat,
+) -> Result
+where
+    T: std::io::Write + termcolor::WriteColor,
+{
+    let (range, terms) = match terms.split_first() {
+        Some((first, rest)) => match parse_priority_range(first) {
+            Some(range) => (range, rest),
+            None => (('A', 'Z'), terms),
+        },
+        None => (('A', 'Z'), terms),
+    };
+    info!("Listing priorities {}-{}", range.0, range.1);
+    list_tasks(terms, buf, ctx, false, format, None, "TODO", |t| {
+        t.priority_prefix()
+            .is_some_and(|p| (range.0..=range.1).contains(&p))
+    })
+}
+
+/// List tasks from a file in the todo.txt directory, or the names of the
+/// files in the directory if `src` is `None`
+pub fn list_file<T>(
+    src: Option<&str>,
+    terms: &[String],
+    buf: &mut T,
+    ctx: &mut AppContext,
+) -> Result
+where
+    T: std::io::Write + termcolor::WriteColor,
+{
+    let src = match src {
+        Some(s) => s,
+        None => {
+            let dir = resolve_in_todo_dir(ctx, ".")?;
+            let mut files = fs::read_dir(&dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+                .filter_map(|p| p.file_name().map(|f| f.to_string_lossy().into_owned()))
+                .collect::<Vec<String>>();
+            files.sort();
+            writeln!(buf, "Files in the todo.txt directory:")?;
+            for file in files {
+                writeln!(buf, "{}", file)?;
+            }
+            return Ok(());
+        }
+    };
+    let path = resolve_in_todo_dir(ctx, src)?;
+    ctx.tasks = get_tasks_from_file(&path)?;
+    ctx.task_ct = ctx.tasks.len();
+    let prefix = get_prefix(&path);
+    list_tasks(terms, buf, ctx, false, ListFormat::Text, None, &prefix, |_| true)
+}
+
+/// Filter, sort and print tasks with footer labeled by `prefix`.
+/// Only tasks matching `terms` and the `keep` predicate are shown.
+#[allow(clippy::too_many_arguments)]
+fn list_tasks<T, F>(
+    terms: &[String],
+    buf: &mut T,
+    ctx: &mut AppContext,
+    list_all: bool,
+    format: ListFormat,
+    group_by: Option<GroupBy>,
+    prefix: &str,
+    keep: F,
+) -> Result
+where
+    T: std::io::Write + termcolor::WriteColor,
+    F: Fn(&Task) -> bool,
 {
     // TODO: extract filter and sort logic so I don't have to repeat it
     let prefilter_task_ct = ctx.tasks.len();
@@ -24,29 +137,68 @@ where
     // filter based on terms
     if !terms.is_empty() {
         info!("Listing with terms: {:?}", terms);
-        ctx.tasks.filter_terms_regex(terms)?;
-        ctx.done.filter_terms_regex(terms)?;
+        let filter = Filter::parse(terms)?;
+        ctx.tasks.filter(&filter);
+        ctx.done.filter(&filter);
     } else {
         info!("Listing without filter");
     }
+    ctx.tasks.retain(&keep);
+    ctx.done.retain(&keep);
+    // hide tasks deferred until a future threshold date
+    let mut deferred_ct = 0;
+    if ctx.settings.hide_future_threshold.unwrap_or(true) && !ctx.opts.all_thresholds {
+        let today = today();
+        let before = ctx.tasks.len();
+        ctx.tasks.retain(|t| !t.is_deferred(today));
+        deferred_ct = before - ctx.tasks.len();
+    }
     let postfilter_task_ct = ctx.tasks.len();
     let postfilter_done_ct = ctx.done.len();
     if list_all {
         ctx.tasks += ctx.done.clone();
     }
-    ctx.tasks.sort(if ctx.opts.sort_by.is_none() {
-        &[SortBy::Raw]
-    } else {
-        &ctx.opts.sort_by.as_ref().unwrap()
-    });
+    let keys = match (&ctx.opts.sort_by, &ctx.settings.default_sort) {
+        (Some(keys), _) => keys.clone(),
+        (None, Some(spec)) => parse_sort_keys(spec).context("reading default_sort setting")?,
+        (None, None) => vec![SortKey::asc(SortBy::Raw)],
+    };
+    ctx.tasks.sort(&keys, ctx.settings.sort_missing.unwrap_or(Missing::Last));
+    if format != ListFormat::Text {
+        let tasks = ctx.tasks.iter().map(JsonTask::from).collect::<Vec<JsonTask>>();
+        let summary = Summary {
+            label:      prefix.into(),
+            shown:      postfilter_task_ct,
+            total:      if list_all { prefilter_task_ct } else { prefilter_task_ct - blank_tasks },
+            done_shown: list_all.then_some(postfilter_done_ct),
+            done_total: list_all.then_some(prefilter_done_ct),
+            deferred:   deferred_ct,
+        };
+        return write_json(buf, format, "tasks", &tasks, &summary);
+    }
     // fill buffer with formatted (colored) output
-    format_buffer(buf, &ctx)?;
+    match group_by {
+        Some(by) => {
+            let heading = get_colors_from_style("heading", ctx)?;
+            for (i, group) in group_tasks(&ctx.tasks, by).iter().enumerate() {
+                if i > 0 {
+                    writeln!(buf)?;
+                }
+                buf.set_color(&heading)?;
+                write!(buf, "{} ({})", group.name, group.tasks.len())?;
+                buf.reset()?;
+                writeln!(buf)?;
+                format_tasks(buf, group.tasks.iter().copied(), ctx)?;
+            }
+        }
+        None => format_buffer(buf, ctx)?,
+    }
     // write footer
     if list_all {
         writeln!(
             buf,
-            "--\nTODO: {} of {} tasks shown",
-            postfilter_task_ct, prefilter_task_ct,
+            "--\n{}: {} of {} tasks shown",
+            prefix, postfilter_task_ct, prefilter_task_ct,
         )?;
         writeln!(
             buf,
@@ -62,10 +214,18 @@ where
     } else {
         writeln!(
             buf,
-            "--\nTODO: {} of {} tasks shown",
+            "--\n{}: {} of {} tasks shown",
+            prefix,
             postfilter_task_ct,
             prefilter_task_ct - blank_tasks,
         )?;
     }
+    if deferred_ct > 0 {
+        writeln!(
+            buf,
+            "{}: {} deferred task(s) hidden (use --all-thresholds to show)",
+            prefix, deferred_ct,
+        )?;
+    }
     Ok(())
 }
@@ -0,0 +1,69 @@
+//! # Move tasks between files in the todo.txt directory
+use crate::{
+    config::AppContext,
+    file::{get_tasks_from_file, resolve_in_todo_dir, write_buf_to_file},
+    prelude::*,
+    util,
+};
+
+/// Move task on line `item` of `src` (todo.txt if `None`) to the end of `dest`.
+/// Returns false if either file or the task doesn't exist.
+pub fn mv(item: usize, dest: &str, src: Option<&str>, ctx: &mut AppContext) -> Result<bool> {
+    let dest = resolve_in_todo_dir(ctx, dest)?;
+    let src = match src {
+        Some(s) => resolve_in_todo_dir(ctx, s)?,
+        None => ctx.todo_file.clone(),
+    };
+    if !src.is_file() {
+        println!("TODO: Source file {} does not exist.", src.display());
+        return Ok(false);
+    }
+    if !dest.is_file() {
+        println!("TODO: Destination file {} does not exist.", dest.display());
+        return Ok(false);
+    }
+    if src.canonicalize()? == dest.canonicalize()? {
+        bail!("source and destination are the same file: {}", src.display());
+    }
+    let mut tasks = if src == ctx.todo_file {
+        ctx.tasks.clone()
+    } else {
+        get_tasks_from_file(&src)?
+    };
+    let task = match tasks.get_by_id_mut(item) {
+        Some(t) => t,
+        None => {
+            println!("{}: No such item in {}.", item, src.display());
+            return Ok(false);
+        }
+    };
+    let prompt = format!(
+        "Move '{}' from {} to {}? (y/n)\n",
+        task.raw,
+        src.display(),
+        dest.display()
+    );
+    if !ctx.opts.force && !util::ask_user_yes_no(&prompt)? {
+        println!("TODO: No tasks moved.");
+        return Ok(true);
+    }
+    let moved = task.clone();
+    *task = task.clear();
+    if ctx.opts.remove_blank_lines {
+        tasks.retain(|t| !t.is_blank());
+    }
+    info!("Moving {} from {:?} to {:?}", moved, src, dest);
+    write_buf_to_file(moved.raw.as_str(), &dest, true)?;
+    write_buf_to_file(tasks.to_raw_string(), &src, false)?;
+    if src == ctx.todo_file {
+        ctx.tasks = tasks;
+    }
+    println!("{}", moved);
+    println!(
+        "TODO: {} moved from '{}' to '{}'.",
+        item,
+        src.display(),
+        dest.display()
+    );
+    Ok(true)
+}
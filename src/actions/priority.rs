@@ -0,0 +1,72 @@
+//! # Add or remove task priorities
+use crate::{config::AppContext, prelude::*};
+
+const PRI_USAGE: &str = "\
+usage: todors pri ITEM# PRIORITY
+note: PRIORITY must be anywhere from A to Z.";
+
+/// Parse user input into an uppercase priority letter
+fn parse_priority(priority: &str) -> Option<char> {
+    let mut chars = priority.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Set priority of task on line `item`. Returns false if nothing could be done.
+pub fn pri(item: usize, priority: &str, ctx: &mut AppContext) -> Result<bool> {
+    let new_pri = match parse_priority(priority) {
+        Some(p) => p,
+        None => {
+            println!("{}", PRI_USAGE);
+            return Ok(false);
+        }
+    };
+    let task = match ctx.tasks.get_by_id_mut(item) {
+        Some(t) => t,
+        None => {
+            println!("TODO: No task {}.", item);
+            return Ok(false);
+        }
+    };
+    let old_pri = task.priority_prefix();
+    if old_pri == Some(new_pri) {
+        println!("TODO: {} already prioritized ({}).", item, new_pri);
+        return Ok(true);
+    }
+    *task = task.with_priority(Some(new_pri));
+    info!("Task after prioritizing: {}", task.raw);
+    println!("{}", task);
+    match old_pri {
+        Some(old) => println!(
+            "TODO: {} re-prioritized from ({}) to ({}).",
+            item, old, new_pri
+        ),
+        None => println!("TODO: {} prioritized ({}).", item, new_pri),
+    }
+    Ok(true)
+}
+
+/// Remove priority from tasks on lines `items`. Stops at the first line that
+/// doesn't exist and returns false.
+pub fn depri(items: &[usize], ctx: &mut AppContext) -> Result<bool> {
+    for &item in items {
+        let task = match ctx.tasks.get_by_id_mut(item) {
+            Some(t) => t,
+            None => {
+                println!("TODO: No task {}.", item);
+                return Ok(false);
+            }
+        };
+        if task.priority_prefix().is_none() {
+            println!("TODO: {} is not prioritized.", item);
+            continue;
+        }
+        *task = task.with_priority(None);
+        info!("Task after deprioritizing: {}", task.raw);
+        println!("{}", task);
+        println!("TODO: {} deprioritized.", item);
+    }
+    Ok(true)
+}
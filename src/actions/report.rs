@@ -0,0 +1,111 @@
+//! # Keep a history of open and done task counts in report.txt
+use crate::{
+    actions::archive::archive,
+    config::AppContext,
+    file::{get_done, read_file_to_string, write_buf_to_file},
+    prelude::*,
+};
+use chrono::Local;
+
+/// Characters used to draw the sparkline, from lowest to highest
+const SPARKS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Single line of report.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReportLine {
+    timestamp: String,
+    open:      usize,
+    done:      usize,
+}
+
+impl ReportLine {
+    /// Parse line in the form `YYYY-MM-DDTHH:MM:SS <open> <done>`
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let timestamp = fields.next()?.to_string();
+        let open = fields.next()?.parse().ok()?;
+        let done = fields.next()?.parse().ok()?;
+        Some(Self {
+            timestamp,
+            open,
+            done,
+        })
+    }
+}
+
+/// Read report file, which may not exist yet
+fn read_report(ctx: &AppContext) -> Result<String> {
+    if !ctx.report_file.exists() {
+        return Ok(String::new());
+    }
+    read_file_to_string(&ctx.report_file)
+}
+
+/// Archive done tasks, then append the current number of open and done
+/// tasks to the report file if they changed since the last report.
+pub fn report(ctx: &mut AppContext) -> Result {
+    archive(ctx)?;
+    get_done(ctx)?;
+    // blank lines kept to preserve line numbers aren't tasks
+    let new_data = format!(
+        "{} {}",
+        ctx.tasks.iter().filter(|t| !t.is_blank()).count(),
+        ctx.done.iter().filter(|t| !t.is_blank()).count()
+    );
+    let contents = read_report(ctx)?;
+    if let Some(last) = contents.lines().last() {
+        if last.split_once(' ').map(|(_, data)| data) == Some(new_data.as_str()) {
+            println!("{}", last);
+            println!("TODO: Report file is up-to-date.");
+            return Ok(());
+        }
+    }
+    let new_report = format!("{} {}", Local::now().format("%Y-%m-%dT%H:%M:%S"), new_data);
+    write_buf_to_file(new_report.as_str(), &ctx.report_file, true)?;
+    println!("{}", new_report);
+    println!("TODO: Report file updated.");
+    Ok(())
+}
+
+/// Draw sparkline scaled between the smallest and largest value
+fn sparkline(values: &[usize]) -> String {
+    let min = values.iter().copied().min().unwrap_or_default();
+    let max = values.iter().copied().max().unwrap_or_default();
+    values
+        .iter()
+        .map(|v| {
+            if max == min {
+                return SPARKS[0];
+            }
+            SPARKS[(v - min) * (SPARKS.len() - 1) / (max - min)]
+        })
+        .collect()
+}
+
+/// Print report history as a table followed by a sparkline of open tasks
+pub fn show_report<W>(buf: &mut W, ctx: &AppContext) -> Result
+where
+    W: std::io::Write + termcolor::WriteColor,
+{
+    let lines = read_report(ctx)?
+        .lines()
+        .filter_map(|l| {
+            let parsed = ReportLine::parse(l);
+            if parsed.is_none() && !l.trim().is_empty() {
+                debug!("Skipping invalid report line: {:?}", l);
+            }
+            parsed
+        })
+        .collect::<Vec<ReportLine>>();
+    if lines.is_empty() {
+        writeln!(buf, "TODO: Report file is empty.")?;
+        return Ok(());
+    }
+    writeln!(buf, "{:<19}  {:>5}  {:>5}", "Date", "Open", "Done")?;
+    for line in &lines {
+        writeln!(buf, "{:<19}  {:>5}  {:>5}", line.timestamp, line.open, line.done)?;
+    }
+    let open = lines.iter().map(|l| l.open).collect::<Vec<usize>>();
+    writeln!(buf, "--\nOpen: {}", sparkline(&open))?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! # List projects and contexts used in tasks
+use crate::{
+    app::ListFormat,
+    config::AppContext,
+    file::get_done,
+    filter::Filter,
+    json::{write_json, JsonTag},
+    prelude::*,
+    task::Task,
+};
+use std::collections::{BTreeMap, HashSet};
+
+/// Kind of tag to collect from tasks
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TagKind {
+    /// `+project`
+    Project,
+    /// `@context`
+    Context,
+}
+
+impl TagKind {
+    /// Character that marks the tag in a task
+    pub(crate) fn sigil(self) -> char {
+        match self {
+            Self::Project => '+',
+            Self::Context => '@',
+        }
+    }
+
+    /// Parsed tags of this kind (lowercased by todo_txt)
+    fn parsed(self, task: &Task) -> &[String] {
+        match self {
+            Self::Project => &task.parsed.projects,
+            Self::Context => &task.parsed.contexts,
+        }
+    }
+
+    /// Unique tags of this kind in a task, as written in the raw line, in
+    /// order of first occurrence
+    pub(crate) fn words(self, task: &Task) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        task.raw
+            .split_whitespace()
+            .filter(|w| {
+                w.strip_prefix(self.sigil())
+                    .is_some_and(|t| self.parsed(task).contains(&t.to_lowercase()))
+            })
+            .filter(|w| seen.insert(*w))
+            .collect()
+    }
+}
+
+/// Print sorted, unique tags found in tasks matching `terms`.
+///
+/// If `all` is true, tasks in done.txt are included. If `count` is true,
+/// each tag is followed by the number of open and done tasks using it. JSON
+/// formats always include the counts, taken from the same tasks.
+pub fn list_tags<W>(
+    kind: TagKind,
+    terms: &[String],
+    all: bool,
+    count: bool,
+    format: ListFormat,
+    buf: &mut W,
+    ctx: &mut AppContext,
+) -> Result
+where
+    W: std::io::Write + termcolor::WriteColor,
+{
+    if all {
+        get_done(ctx)?;
+    }
+    let mut tasks = ctx.tasks.clone();
+    tasks += ctx.done.clone();
+    tasks.retain(|t| !t.is_blank());
+    tasks.filter(&Filter::parse(terms)?);
+    // tag => (open, done)
+    let mut tags: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for task in tasks.iter() {
+        for word in kind.words(task) {
+            let ct = tags.entry(word).or_default();
+            if task.parsed.finished {
+                ct.1 += 1;
+            } else {
+                ct.0 += 1;
+            }
+        }
+    }
+    if format != ListFormat::Text {
+        let tags = tags
+            .iter()
+            .map(|(tag, (open, done))| JsonTag { tag, open: *open, done: *done })
+            .collect::<Vec<JsonTag>>();
+        let summary = serde_json::json!({ "tags": tags.len() });
+        return write_json(buf, format, "tags", &tags, &summary);
+    }
+    let width = tags.keys().map(|t| t.len()).max().unwrap_or_default();
+    for (tag, (open, done)) in tags {
+        if count {
+            writeln!(buf, "{:width$}  {} open  {} done", tag, open, done, width = width)?;
+        } else {
+            writeln!(buf, "{}", tag)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    /// Repeated tags are listed once, in order of first occurrence
+    fn tag_words_unique() {
+        let task = Task::new(1, "Plan +b trip +a @home +b +A");
+        assert_eq!(TagKind::Project.words(&task), vec!["+b", "+a", "+A"]);
+        assert_eq!(TagKind::Context.words(&task), vec!["@home"]);
+    }
+}
@@ -1,6 +1,6 @@
 //! Build cli app using #[derive(Clap)]
 
-use crate::task::SortBy;
+use crate::task::{SortBy, SortKey};
 use clap::{AppSettings, ArgEnum, IntoApp, Parser};
 use clap_complete::{generate, shells::*};
 
@@ -92,9 +92,33 @@ pub struct Opt {
     /// Don't prepend current date to new task.
     #[clap(name = "T", short, overrides_with("t"), help_heading = FLAG_HDG)]
     pub no_date_on_add:        bool,
-    /// Sort tasks by property
-    #[clap(short, arg_enum)]
-    pub sort_by:                  Option<Vec<SortBy>>,
+    /// Show tasks with a threshold date in the future.
+    ///
+    /// By default, tasks deferred with a `t:` date after today are hidden
+    /// from listings.
+    #[clap(long, global = true, help_heading = FLAG_HDG)]
+    pub all_thresholds:        bool,
+    /// Sort tasks by properties, e.g. `-s due:desc,priority,project`.
+    ///
+    /// Each KEY is sorted ascending, or descending if followed by `:desc`;
+    /// later keys sort tasks that are equal by earlier ones. Overrides the
+    /// `default_sort` setting.
+    ///
+    /// Keys: body, complete-date, completed, context, create-date (created),
+    /// due-date (due), id, priority (pri), project, raw and
+    /// threshold-date (threshold, t).
+    #[clap(short, value_name = "KEY[:desc]", use_value_delimiter = true)]
+    pub sort_by:                  Option<Vec<SortKey>>,
+    /// Template for each line of task listings.
+    ///
+    /// Overrides the `list_format` setting. Placeholders are {id}, {raw},
+    /// {done}, {pri}, {subject}, {projects}, {contexts}, {tags}, {due}, {t},
+    /// {created} and {completed}. Add `:>3`, `:<10` or `:^5` to pad a
+    /// placeholder, or a strftime format to dates, e.g.
+    /// `{id:>3} {pri} {subject} {projects} {due:%b %d}`.
+    /// The default, `{id} {raw}`, is the todo.sh layout.
+    #[clap(long, global = true, value_name = "TEMPLATE")]
+    pub format_template:       Option<String>,
     #[clap(subcommand)]
     pub cmd:                   Option<Commands>,
 }
@@ -131,7 +155,28 @@ use \"TERM1\\|TERM2\\|...\" (with quotes), or TERM1|TERM2
 (unquoted).
 
 Hide all tasks that contain TERM(s) preceded by a minus
-sign (i.e. -TERM).";
+sign (i.e. -TERM). Terms can be grouped with parentheses,
+e.g. \"-(TERM1 | TERM2)\".
+
+Besides text, TERM can match fields of the task:
+  +project, @context   task has project or context
+  pri:A, pri:A-C       task has priority
+  done:yes, done:no    task is or isn't completed
+  due<2026-11-01       compare due, t, created or completed
+                       date using <, <=, >, >=, = or !=
+
+Dates can also be relative to today: today, tomorrow,
+yesterday, a weekday (monday or mon), eow, eom, eoy
+(end of week, month or year), or an offset such as
++7d, -30d, +2w, +1m or -1y. For example:
+  due:today, due<+7d, created>-30d, t<=today";
+
+const LSP_TERM: &str = "\
+Optional priority or range of priorities followed by
+terms to filter task list by.
+
+For example, `listpri A-C phone` displays tasks with
+priority A, B or C that contain \"phone\".";
 
 #[derive(clap::Subcommand, Debug, Clone, Eq, PartialEq)]
 pub enum Commands {
@@ -146,8 +191,17 @@ pub enum Commands {
         tasks: Vec<String>,
     },
     /// Adds a line of text to any file located in the todo.txt directory.
-    Addto,
+    ///
+    /// For example, `todors addto someday.txt \"Learn to juggle\"`.
+    Addto {
+        /// File in the todo.txt directory to add TASK to.
+        #[clap(name = "DEST")]
+        dest: String,
+        #[clap(name = "TASK", long_help = ADD_TASK)]
+        task: String,
+    },
     /// Adds text to end of task.
+    #[clap(alias = "app")]
     Append {
         /// Line number of todo.txt to append TEXT.
         #[clap(name = "ITEM")]
@@ -158,6 +212,11 @@ pub enum Commands {
     },
     /// Moves all done tasks from todo.txt to done.txt and removes blank lines.
     Archive,
+    /// Lists or restores backups taken before todo.txt is changed.
+    Backup {
+        #[clap(subcommand)]
+        cmd: BackupCommand,
+    },
     /// Generates shell completions to stdout.
     Complete {
         /// Generate completions for this shell.
@@ -165,7 +224,13 @@ pub enum Commands {
         shell: Shell,
     },
     /// Removes duplicate lines from todo.txt.
-    Deduplicate,
+    ///
+    /// Duplicates are left blank to preserve line numbers unless -n is given.
+    Deduplicate {
+        /// Ignore case and extra whitespace when comparing tasks.
+        #[clap(long)]
+        normalize: bool,
+    },
     /// Deletes a task or part of a task from todo.txt.
     #[clap(alias = "rm")]
     Del {
@@ -178,28 +243,276 @@ pub enum Commands {
     /// Deprioritizes (removes the priority) from the task(s) on line ITEM in todo.txt.
     #[clap(alias = "dp")]
     Depri {
-        /// Line number in todo.txt to remove priority.
-        #[clap(name = "ITEM")]
+        /// Line number(s) in todo.txt to remove priority.
+        ///
+        /// Multiple items may be separated by spaces or commas.
+        #[clap(name = "ITEM", required = true, use_value_delimiter = true)]
+        items: Vec<usize>,
+    },
+    /// Marks task(s) on line ITEM as done in todo.txt.
+    #[clap(alias = "done")]
+    Do {
+        /// Line number(s) in todo.txt to mark as done.
+        ///
+        /// Multiple items may be separated by spaces or commas.
+        #[clap(name = "ITEM", required = true, use_value_delimiter = true)]
         items: Vec<usize>,
     },
+    /// Writes tasks in todo.txt to stdout in another format.
+    ///
+    /// Tasks are written in line order.
+    // Set on the command rather than TERM, which would also take the flags
+    // given after it, so that `-a` still works after a `-TERM`.
+    #[clap(allow_hyphen_values = true)]
+    Export {
+        #[clap(name = "TERM", long_help = LS_TERM)]
+        terms:   Vec<String>,
+        /// Output format.
+        #[clap(long, arg_enum, default_value = "csv")]
+        format:  ExportFormat,
+        /// Fields to write as columns, separated by commas.
+        ///
+        /// Defaults to the `export_columns` setting, or
+        /// id,priority,create-date,body,project,context,due-date,threshold-date.
+        #[clap(long, arg_enum, use_value_delimiter = true)]
+        columns: Option<Vec<SortBy>>,
+        /// Include tasks from done.txt.
+        #[clap(short, long)]
+        all:     bool,
+        /// Write all-day events instead of to-dos with `--format ics`.
+        #[clap(long)]
+        events:  bool,
+    },
+    /// Adds tasks from a CSV or TSV file to todo.txt.
+    ///
+    /// The first row names the columns, using the field names of
+    /// `export --columns`. A `raw` column is used as the whole line; otherwise
+    /// the line is built from the other columns. Unknown columns become
+    /// `key:value` tags.
+    Import {
+        /// File to read tasks from.
+        #[clap(name = "FILE", parse(from_os_str))]
+        file:    std::path::PathBuf,
+        /// Input format [default: tsv for .tsv files, otherwise csv].
+        #[clap(long, arg_enum)]
+        format:  Option<ExportFormat>,
+        /// Show the tasks that would be added without changing todo.txt.
+        #[clap(long)]
+        dry_run: bool,
+    },
     /// Displays all the lines in todo.txt with optional filtering.
     ///
     /// Sorted by priority with line numbers.
     #[clap(alias = "ls")]
     List {
-        #[clap(name = "TERM", long_help = LS_TERM)]
-        terms: Vec<String>,
+        #[clap(name = "TERM", long_help = LS_TERM, allow_hyphen_values = true)]
+        terms:    Vec<String>,
+        /// Output format; must be given before TERM.
+        #[clap(long, arg_enum, default_value = "text")]
+        format:   ListFormat,
+        /// Show tasks in sections with a heading for each value of the field.
+        ///
+        /// A task with several projects or contexts is shown under each.
+        /// Must be given before TERM; ignored by JSON formats.
+        #[clap(long, arg_enum)]
+        group_by: Option<GroupBy>,
     },
     /// Displays all lines in todo.txt AND done.txt with optional filtering.
     ///
     /// Sorted by priority with line numbers.
     #[clap(alias = "lsa")]
     Listall {
-        #[clap(name = "TERM", long_help = LS_TERM)]
+        #[clap(name = "TERM", long_help = LS_TERM, allow_hyphen_values = true)]
+        terms:    Vec<String>,
+        /// Output format; must be given before TERM.
+        #[clap(long, arg_enum, default_value = "text")]
+        format:   ListFormat,
+        /// Show tasks in sections with a heading for each value of the field.
+        ///
+        /// A task with several projects or contexts is shown under each.
+        /// Must be given before TERM; ignored by JSON formats.
+        #[clap(long, arg_enum)]
+        group_by: Option<GroupBy>,
+    },
+    /// Displays all the lines in SRC file located in the todo.txt directory.
+    ///
+    /// Sorted by priority with line numbers. If no SRC is given, lists the
+    /// files in the todo.txt directory.
+    #[clap(alias = "lf")]
+    Listfile {
+        /// File in the todo.txt directory to list.
+        #[clap(name = "SRC")]
+        src:   Option<String>,
+        #[clap(name = "TERM", long_help = LS_TERM, allow_hyphen_values = true)]
         terms: Vec<String>,
     },
+    /// Displays all tasks prioritized PRIORITIES, with optional filtering.
+    ///
+    /// PRIORITIES can be a single priority (A) or a range (A-C). If no
+    /// PRIORITIES are given, lists all prioritized tasks.
     #[clap(alias = "lsp")]
-    Listpri { priorities: Vec<String> },
+    Listpri {
+        #[clap(name = "PRIORITIES|TERM", long_help = LSP_TERM, allow_hyphen_values = true)]
+        terms:  Vec<String>,
+        /// Output format; must be given before TERM.
+        #[clap(long, arg_enum, default_value = "text")]
+        format: ListFormat,
+    },
+    /// Lists the projects used in todo.txt, with optional filtering.
+    #[clap(alias = "lsprj", allow_hyphen_values = true)]
+    Listproj {
+        #[clap(name = "TERM", long_help = LS_TERM)]
+        terms:  Vec<String>,
+        /// Include projects from done.txt.
+        #[clap(short, long)]
+        all:    bool,
+        /// Show number of open and done tasks for each project.
+        #[clap(short, long)]
+        count:  bool,
+        /// Output format.
+        #[clap(long, arg_enum, default_value = "text")]
+        format: ListFormat,
+    },
+    /// Lists the contexts used in todo.txt, with optional filtering.
+    #[clap(alias = "lsc", allow_hyphen_values = true)]
+    Listcon {
+        #[clap(name = "TERM", long_help = LS_TERM)]
+        terms:  Vec<String>,
+        /// Include contexts from done.txt.
+        #[clap(short, long)]
+        all:    bool,
+        /// Show number of open and done tasks for each context.
+        #[clap(short, long)]
+        count:  bool,
+        /// Output format.
+        #[clap(long, arg_enum, default_value = "text")]
+        format: ListFormat,
+    },
+    /// Moves a line from source text file (SRC) to destination text file (DEST).
+    ///
+    /// Both source and destination file must be located in the directory
+    /// defined in the configuration. When SRC is not defined, it's by default
+    /// todo.txt.
+    #[clap(alias = "mv")]
+    Move {
+        /// Line number in SRC to move.
+        #[clap(name = "ITEM")]
+        item: usize,
+        /// File in the todo.txt directory to move ITEM to.
+        #[clap(name = "DEST")]
+        dest: String,
+        /// File in the todo.txt directory to move ITEM from.
+        #[clap(name = "SRC")]
+        src:  Option<String>,
+    },
+    /// Adds PRIORITY to task on line ITEM.
+    ///
+    /// If the task is already prioritized, replaces current priority with new
+    /// PRIORITY.
+    #[clap(alias = "p")]
+    Pri {
+        /// Line number in todo.txt to prioritize.
+        #[clap(name = "ITEM")]
+        item:     usize,
+        /// Priority letter from A to Z.
+        #[clap(name = "PRIORITY")]
+        priority: String,
+    },
+    /// Adds text to beginning of task.
+    ///
+    /// Existing priority and creation date are kept at the front.
+    #[clap(alias = "prep")]
+    Prepend {
+        /// Line number of todo.txt to prepend TEXT.
+        #[clap(name = "ITEM")]
+        item: usize,
+        /// Text to prepend to ITEM.
+        #[clap(name = "TEXT")]
+        text: String,
+    },
+    /// Adds the number of open tasks and done tasks to report.txt.
+    ///
+    /// Done tasks are archived first.
+    Report {
+        /// Show the report history instead of adding to it.
+        #[clap(long)]
+        show: bool,
+    },
+    /// Replaces task on line ITEM with TEXT.
+    ///
+    /// Existing priority and creation date are kept unless TEXT
+    /// contains its own.
+    Replace {
+        /// Line number of todo.txt to replace.
+        #[clap(name = "ITEM")]
+        item: usize,
+        /// New text of ITEM.
+        #[clap(name = "TEXT")]
+        text: String,
+    },
+    /// Reapplies the last STEPS changes reverted by undo.
+    Redo {
+        /// Number of changes to redo.
+        #[clap(name = "STEPS", default_value = "1")]
+        steps: usize,
+    },
+    /// Reverts the last STEPS changes made to todo.txt and related files.
+    Undo {
+        /// Number of changes to undo.
+        #[clap(name = "STEPS", default_value = "1")]
+        steps: usize,
+        /// List recorded changes instead of undoing them.
+        #[clap(long)]
+        list:  bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug, Clone, Eq, PartialEq)]
+pub enum BackupCommand {
+    /// Lists backups, newest first.
+    #[clap(alias = "ls")]
+    List,
+    /// Replaces the file with backup NUMBER from `backup list`.
+    Restore {
+        /// Number of backup shown by `backup list`.
+        #[clap(name = "NUMBER")]
+        number: usize,
+    },
+}
+
+/// Output format of listing commands
+#[derive(ArgEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ListFormat {
+    /// Colored lines as printed by todo.sh
+    Text,
+    /// Single JSON object
+    Json,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// Field to group tasks by in `list` and `listall`
+#[derive(ArgEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GroupBy {
+    Project,
+    Context,
+    Priority,
+    Due,
+}
+
+/// Output format of `export`
+#[derive(ArgEnum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// Comma-separated values
+    Csv,
+    /// Tab-separated values
+    Tsv,
+    /// iCalendar with tasks that have a due date
+    Ics,
+    /// Markdown task lists grouped by project
+    Markdown,
+    /// HTML page with task lists grouped by project
+    Html,
 }
 
 #[derive(ArgEnum, Copy, Clone, Debug, Eq, PartialEq)]
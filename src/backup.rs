@@ -0,0 +1,319 @@
+//! Timestamped backups of files taken before they are changed
+use crate::{
+    config::AppContext,
+    file::{read_file_to_string, resolve_in_todo_dir, write_buf_to_file},
+    journal::Journal,
+    prelude::*,
+};
+use chrono::{Local, NaiveDateTime};
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// Number of backups kept per file if not set in config
+const DEFAULT_KEEP: usize = 20;
+/// Format of the timestamp added to backup file names
+const TIMESTAMP_FMT: &str = "%Y%m%dT%H%M%S%.3f";
+
+/// Backup copy of a file in the backup directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path:      PathBuf,
+    /// Name of the file that was backed up, e.g. `todo.txt`
+    pub file_name: String,
+    pub time:      NaiveDateTime,
+}
+
+impl Backup {
+    /// Parse backup file name in the form `<file_name>.<timestamp>`
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_string();
+        let (file_name, timestamp) = name.rsplit_once('.').and_then(|(rest, ms)| {
+            let (file_name, ts) = rest.rsplit_once('.')?;
+            Some((file_name.to_string(), format!("{}.{}", ts, ms)))
+        })?;
+        let time = NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FMT).ok()?;
+        Some(Self {
+            path,
+            file_name,
+            time,
+        })
+    }
+
+    /// File this backup should be restored to
+    fn original(&self, ctx: &AppContext) -> Result<PathBuf> {
+        for file in &[&ctx.todo_file, &ctx.done_file] {
+            if file.file_name().is_some_and(|f| f == self.file_name.as_str()) {
+                return Ok(file.to_path_buf());
+            }
+        }
+        resolve_in_todo_dir(ctx, &self.file_name)
+    }
+}
+
+/// Where and how long to keep backups, and the files already backed up
+#[derive(Debug)]
+struct Policy {
+    dir:       PathBuf,
+    keep:      usize,
+    max_age:   Option<Duration>,
+    /// Timestamp shared by all backups taken by this process
+    timestamp: String,
+    /// Files that are backed up at most once, or never
+    seen:      HashSet<PathBuf>,
+}
+
+/// Policy set by [`enable`] and used by [`before_write`]
+static POLICY: Mutex<Option<Policy>> = Mutex::new(None);
+
+/// Back up files before they are first written by this process, using the
+/// backup settings of `ctx`. The journal is never backed up.
+pub fn enable(ctx: &AppContext) {
+    let keep = ctx.settings.backup_keep.unwrap_or(DEFAULT_KEEP);
+    let policy = (keep > 0).then(|| Policy {
+        dir:       ctx.backup_dir.clone(),
+        keep,
+        max_age:   ctx
+            .settings
+            .backup_max_age_days
+            .map(|d| Duration::from_secs(d * 24 * 60 * 60)),
+        timestamp: Local::now().format(TIMESTAMP_FMT).to_string(),
+        seen:      HashSet::from([Journal::path(ctx)]),
+    });
+    *POLICY.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// Back up `file` if backups are enabled and it wasn't backed up yet.
+/// Called by `write_buf_to_file` before changing a file.
+pub fn before_write(file: &Path) -> Result {
+    match POLICY.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        Some(policy) => policy.backup(file),
+        None => Ok(()),
+    }
+}
+
+impl Policy {
+    /// Save current contents of `file`, then remove old backups of it.
+    /// Missing and empty files aren't backed up.
+    fn backup(&mut self, file: &Path) -> Result {
+        if !self.seen.insert(file.to_path_buf()) {
+            return Ok(());
+        }
+        let contents = match fs::read_to_string(file) {
+            Ok(c) if !c.is_empty() => c,
+            _ => return Ok(()),
+        };
+        let file_name = file
+            .file_name()
+            .ok_or_else(|| format_err!("{:?} is not a file path", file))?
+            .to_string_lossy();
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating backup directory {:?}", self.dir))?;
+        let path = self.dir.join(format!("{}.{}", file_name, self.timestamp));
+        fs::write(&path, contents).with_context(|| format!("writing backup {:?}", path))?;
+        debug!("Backed up {:?} to {:?}", file, path);
+        self.prune(&file_name)
+    }
+
+    /// Remove backups of `file_name` beyond the newest `keep`, and any older
+    /// than the maximum age
+    fn prune(&self, file_name: &str) -> Result {
+        let is_expired = |path: &Path| {
+            self.max_age.is_some_and(|max| {
+                fs::metadata(path)
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| SystemTime::now().duration_since(t).ok())
+                    .is_some_and(|age| age > max)
+            })
+        };
+        let backups = get_backups(&self.dir)?
+            .into_iter()
+            .filter(|b| b.file_name == file_name);
+        for (i, backup) in backups.enumerate() {
+            if i >= self.keep || is_expired(&backup.path) {
+                debug!("Removing old backup {:?}", backup.path);
+                fs::remove_file(&backup.path)
+                    .with_context(|| format!("removing old backup {:?}", backup.path))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All backups in the backup directory, newest first. Backups taken at the
+/// same time are ordered by file name so `backup list` numbers are stable.
+pub fn get_backups(dir: &Path) -> Result<Vec<Backup>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut backups = fs::read_dir(dir)
+        .with_context(|| format!("reading backup directory {:?}", dir))?
+        .filter_map(|e| e.ok().and_then(|e| Backup::from_path(e.path())))
+        .collect::<Vec<Backup>>();
+    backups.sort_by_key(|b| (Reverse(b.time), b.file_name.clone()));
+    Ok(backups)
+}
+
+/// Print numbered list of backups, newest first
+pub fn list<W>(buf: &mut W, ctx: &AppContext) -> Result
+where
+    W: std::io::Write + termcolor::WriteColor,
+{
+    let backups = get_backups(&ctx.backup_dir)?;
+    let width = backups.len().to_string().len();
+    for (i, backup) in backups.iter().enumerate() {
+        let lines = read_file_to_string(&backup.path)?.lines().count();
+        writeln!(
+            buf,
+            "{:>width$}  {}  {}  ({} lines)",
+            i + 1,
+            backup.time.format("%Y-%m-%d %H:%M:%S"),
+            backup.file_name,
+            lines,
+            width = width
+        )?;
+    }
+    writeln!(
+        buf,
+        "--\nBACKUP: {} backups in {}",
+        backups.len(),
+        ctx.backup_dir.display()
+    )?;
+    Ok(())
+}
+
+/// Replace the original file with backup number `n` from `list`
+pub fn restore(n: usize, ctx: &AppContext) -> Result<bool> {
+    let backups = get_backups(&ctx.backup_dir)?;
+    let backup = match n.checked_sub(1).and_then(|i| backups.get(i)) {
+        Some(b) => b,
+        None => {
+            println!("TODO: No backup {}.", n);
+            return Ok(false);
+        }
+    };
+    let original = backup.original(ctx)?;
+    write_buf_to_file(read_file_to_string(&backup.path)?, &original, false)?;
+    println!(
+        "TODO: Restored {} from backup {}.",
+        original.display(),
+        backup.time.format("%Y-%m-%d %H:%M:%S")
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch_dir;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    /// Names of the backups in directory, newest first
+    fn backup_names(dir: &Path) -> Vec<String> {
+        get_backups(dir)
+            .unwrap()
+            .iter()
+            .map(|b| b.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn policy(dir: &Path, keep: usize, max_age: Option<Duration>) -> Policy {
+        Policy {
+            dir:       dir.join("backup"),
+            keep,
+            max_age,
+            timestamp: "20261016T120000.000".into(),
+            seen:      HashSet::new(),
+        }
+    }
+
+    #[test]
+    /// Backup names are the file name followed by a timestamp
+    fn backup_name() {
+        let backup = Backup::from_path(PathBuf::from("b/todo.txt.20261016T101112.345")).unwrap();
+        assert_eq!(backup.file_name, "todo.txt");
+        assert_eq!(
+            backup.time.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            "2026-10-16 10:11:12.345"
+        );
+        assert_eq!(Backup::from_path(PathBuf::from("todo.txt")), None);
+        assert_eq!(Backup::from_path(PathBuf::from("todo.txt.bak")), None);
+    }
+
+    #[test]
+    /// Only the newest `keep` backups of a file are kept, each file is backed
+    /// up once, and backups older than the maximum age are removed. Backups
+    /// taken at the same time are listed by file name.
+    fn backup_prune() -> Result {
+        let dir = scratch_dir("backup-prune");
+        let todo = dir.join("todo.txt");
+        fs::write(&todo, "new\n")?;
+        fs::create_dir_all(dir.join("backup"))?;
+        for name in &[
+            "todo.txt.20261014T120000.000",
+            "todo.txt.20261015T120000.000",
+            "done.txt.20261015T120000.000",
+            "done.txt.20261001T120000.000",
+        ] {
+            fs::write(dir.join("backup").join(name), "old\n")?;
+        }
+        let mut keep_two = policy(&dir, 2, None);
+        keep_two.backup(&todo)?;
+        fs::write(&todo, "newer\n")?;
+        keep_two.backup(&todo)?;
+        assert_eq!(backup_names(&keep_two.dir), vec![
+            "todo.txt.20261016T120000.000",
+            "done.txt.20261015T120000.000",
+            "todo.txt.20261015T120000.000",
+            "done.txt.20261001T120000.000",
+        ]);
+        assert_eq!(
+            fs::read_to_string(keep_two.dir.join("todo.txt.20261016T120000.000"))?,
+            "new\n"
+        );
+        let old = File::options()
+            .write(true)
+            .open(keep_two.dir.join("todo.txt.20261015T120000.000"))?;
+        old.set_modified(SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60))?;
+        let mut max_age = policy(&dir, 20, Some(Duration::from_secs(24 * 60 * 60)));
+        max_age.timestamp = "20261017T120000.000".into();
+        max_age.backup(&todo)?;
+        assert_eq!(backup_names(&max_age.dir), vec![
+            "todo.txt.20261017T120000.000",
+            "todo.txt.20261016T120000.000",
+            "done.txt.20261015T120000.000",
+            "done.txt.20261001T120000.000",
+        ]);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    /// Restoring replaces the original file with the numbered backup
+    fn backup_restore() -> Result {
+        let dir = scratch_dir("backup-restore");
+        let ctx = AppContext {
+            todo_file:  dir.join("todo.txt"),
+            done_file:  dir.join("done.txt"),
+            backup_dir: dir.join("backup"),
+            ..Default::default()
+        };
+        fs::write(&ctx.todo_file, "broken\n")?;
+        fs::create_dir_all(&ctx.backup_dir)?;
+        fs::write(ctx.backup_dir.join("todo.txt.20261016T120000.000"), "newest\n")?;
+        fs::write(ctx.backup_dir.join("todo.txt.20261015T120000.000"), "older\n")?;
+        assert!(restore(2, &ctx)?);
+        assert_eq!(fs::read_to_string(&ctx.todo_file)?, "older\n");
+        assert!(!restore(3, &ctx)?);
+        assert_eq!(fs::read_to_string(&ctx.todo_file)?, "older\n");
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
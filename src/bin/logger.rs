@@ -43,7 +43,7 @@ pub fn init_logger(verbose: u8) {
 }
 
 /// Style log level with color
-fn colored_level(style: &mut Style, level: Level) -> StyledValue<String> {
+fn colored_level(style: &mut Style, level: Level) -> StyledValue<'_, String> {
     match level {
         Level::Trace => style.set_color(Color::Black),
         Level::Debug => style.set_color(Color::Cyan),
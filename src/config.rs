@@ -1,5 +1,11 @@
 //! Configure app settings and context object
-use crate::{app::Opt, file::read_file_to_string, prelude::*, style::Style, task::Tasks};
+use crate::{
+    app::Opt,
+    file::read_file_to_string,
+    prelude::*,
+    style::Style,
+    task::{Missing, Tasks},
+};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
@@ -18,16 +24,40 @@ pub struct AppContext {
     pub todo_file:   PathBuf,
     pub done_file:   PathBuf,
     pub report_file: PathBuf,
+    pub backup_dir:  PathBuf,
 }
 
 /// General app settings
 #[derive(Debug, Deserialize, Default)]
 pub struct Settings {
-    pub todo_file:      Option<String>,
-    pub done_file:      Option<String>,
-    pub report_file:    Option<String>,
-    pub date_on_add:    Option<bool>,
-    pub default_action: Option<String>,
+    pub todo_file:             Option<String>,
+    pub done_file:             Option<String>,
+    pub report_file:           Option<String>,
+    pub date_on_add:           Option<bool>,
+    pub default_action:        Option<String>,
+    /// Archive done tasks automatically on completion (default: true)
+    pub auto_archive:          Option<bool>,
+    /// Keep priority of done task as a `pri:X` tag (default: false)
+    pub preserve_priority:     Option<bool>,
+    /// Comma-separated fields written by `export` (default: see `export --help`)
+    pub export_columns:        Option<String>,
+    /// Template for lines of task listings (default: `{id} {raw}`)
+    pub list_format:           Option<String>,
+    /// Sort keys of listings, e.g. `due:desc,priority` (default: `raw`)
+    pub default_sort:          Option<String>,
+    /// Place tasks without a date, priority, project or context `first` or
+    /// `last` when sorting by it (default: last)
+    pub sort_missing:          Option<Missing>,
+    /// Hide tasks with a threshold date after today (default: true)
+    pub hide_future_threshold: Option<bool>,
+    /// Seconds to wait for another todors to release todo.txt (default: 10)
+    pub lock_timeout:          Option<u64>,
+    /// Directory for backups (default: `backup` next to todo.txt)
+    pub backup_dir:            Option<String>,
+    /// Number of backups kept per file; 0 disables backups (default: 20)
+    pub backup_keep:           Option<usize>,
+    /// Remove backups older than this many days (default: keep all)
+    pub backup_max_age_days:   Option<u64>,
 }
 
 /// All configuration settings from toml
@@ -75,6 +105,12 @@ impl AppContext {
             .and_then(|s| shellexpand::env(s).ok())
             .map(|s| PathBuf::from(s.as_ref()))
             .ok_or_else(|| format_err!(ERR))?;
+        self.backup_dir = match &self.settings.backup_dir {
+            Some(dir) => shellexpand::env(dir)
+                .map(|s| PathBuf::from(s.as_ref()))
+                .map_err(|_| format_err!("Error expanding backup dir path"))?,
+            None => self.todo_file.with_file_name("backup"),
+        };
         Ok(())
     }
 }
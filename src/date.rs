@@ -0,0 +1,146 @@
+//! Parse dates written relative to today, e.g. `tomorrow` or `+7d`
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+
+/// Current local date
+pub fn today() -> NaiveDate {
+    Local::today().naive_local()
+}
+
+/// Add months to date, keeping the day of the month where possible and
+/// using the last day of the month otherwise (e.g. Jan 31 + 1m = Feb 28).
+/// Returns `None` if the result is out of range.
+pub fn add_months(date: NaiveDate, months: i32) -> Option<NaiveDate> {
+    let total = (date.year() * 12 + date.month0() as i32).checked_add(months)?;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+}
+
+/// Move date by `n` units, where unit is `d`ays, `w`eeks, `m`onths or `y`ears.
+/// Returns `None` for other units or if the result is out of range.
+pub fn shift(date: NaiveDate, n: i32, unit: char) -> Option<NaiveDate> {
+    match unit {
+        'd' => date.checked_add_signed(Duration::days(n.into())),
+        'w' => date.checked_add_signed(Duration::weeks(n.into())),
+        'm' => add_months(date, n),
+        'y' => add_months(date, n.checked_mul(12)?),
+        _ => None,
+    }
+}
+
+/// Parse offset like `+7d`, `-2w` or `3m` into a count and unit
+pub fn parse_offset(s: &str) -> Option<(i32, char)> {
+    let unit = s.chars().last()?.to_ascii_lowercase();
+    let n = s[..s.len() - unit.len_utf8()].parse::<i32>().ok()?;
+    Some((n, unit))
+}
+
+/// Parse lowercase weekday name or three-letter abbreviation
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    use Weekday::*;
+    [Mon, Tue, Wed, Thu, Fri, Sat, Sun]
+        .into_iter()
+        .find(|&day| s == day_name(day) || s == &day_name(day)[..3])
+}
+
+/// Full lowercase name of weekday
+fn day_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// Parse absolute (`YYYY-MM-DD`) or relative date.
+///
+/// Relative dates are computed from `today` and can be:
+///
+/// * `today`, `tomorrow`, `yesterday`
+/// * weekday name (`monday` or `mon`): next such day, or today if it matches
+/// * `eow`, `eom`, `eoy`: end of the current week (Sunday), month or year
+/// * offset such as `+7d`, `-30d`, `+2w`, `+1m` or `-1y`
+pub fn parse_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let s = s.to_ascii_lowercase();
+    match s.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return Some(today.succ()),
+        "yesterday" => return Some(today.pred()),
+        "eow" => {
+            let days = 6 - today.weekday().num_days_from_monday();
+            return Some(today + Duration::days(days.into()));
+        }
+        "eom" => return add_months(today.with_day(1)?, 1).map(|d| d.pred()),
+        "eoy" => return NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        _ => {}
+    }
+    if let Some(day) = parse_weekday(&s) {
+        let days = (7 + day.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        return Some(today + Duration::days(days.into()));
+    }
+    if s.starts_with(['+', '-']) {
+        let (n, unit) = parse_offset(&s)?;
+        return shift(today, n, unit);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    /// Keywords and offsets relative to Friday 2026-10-16
+    fn relative_dates() {
+        let today = NaiveDate::from_ymd(2026, 10, 16);
+        let date = |s| parse_date(s, today).map(|d| d.to_string());
+        assert_eq!(date("2026-11-01").as_deref(), Some("2026-11-01"));
+        assert_eq!(date("today").as_deref(), Some("2026-10-16"));
+        assert_eq!(date("Tomorrow").as_deref(), Some("2026-10-17"));
+        assert_eq!(date("friday").as_deref(), Some("2026-10-16"));
+        assert_eq!(date("mon").as_deref(), Some("2026-10-19"));
+        assert_eq!(date("eow").as_deref(), Some("2026-10-18"));
+        assert_eq!(date("eom").as_deref(), Some("2026-10-31"));
+        assert_eq!(date("+7d").as_deref(), Some("2026-10-23"));
+        assert_eq!(date("-30d").as_deref(), Some("2026-09-16"));
+        assert_eq!(date("+1m").as_deref(), Some("2026-11-16"));
+        assert_eq!(date("soon"), None);
+        assert_eq!(date("+7x"), None);
+    }
+
+    #[test]
+    /// Adding months clamps to the end of shorter months
+    fn months_clamp() {
+        let jan31 = NaiveDate::from_ymd(2026, 1, 31);
+        assert_eq!(add_months(jan31, 1), NaiveDate::from_ymd_opt(2026, 2, 28));
+        assert_eq!(add_months(jan31, -2), NaiveDate::from_ymd_opt(2025, 11, 30));
+        assert_eq!(add_months(jan31, 12), NaiveDate::from_ymd_opt(2027, 1, 31));
+    }
+
+    #[test]
+    /// Offsets beyond the supported range of dates are invalid
+    fn out_of_range_offsets() {
+        let today = NaiveDate::from_ymd(2026, 10, 16);
+        for s in &[
+            "+99999999d",
+            "-99999999d",
+            "+99999999w",
+            "+2147483647m",
+            "+99999999m",
+            "+300000000y",
+            "-300000000y",
+        ] {
+            assert_eq!(parse_date(s, today), None, "{}", s);
+        }
+        assert_eq!(add_months(today, i32::MAX), None);
+    }
+}
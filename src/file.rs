@@ -1,59 +1,114 @@
 use crate::{
+    backup,
     config::AppContext,
     prelude::*,
     task::{Task, Tasks},
 };
+use fs2::FileExt;
 use std::{
-    fs::OpenOptions,
-    io::{Read, Write},
-    path::Path,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
 };
 
-// TODO: combine get_tasks and get_done since they are 90% the same
 /// Load todo.txt file and parse into Task objects.
-/// If the file doesn't exist, create it.
 pub fn get_tasks(ctx: &mut AppContext) -> Result {
-    read_file_to_string(&ctx.todo_file).map(|b| {
-        ctx.task_ct = 0;
-        ctx.tasks = Tasks(
-            b.lines()
-                .map(|l| {
-                    ctx.task_ct += 1;
-                    Task::new(ctx.task_ct, l)
-                })
-                .collect(),
-        );
-    })
+    ctx.tasks = get_tasks_from_file(&ctx.todo_file)?;
+    ctx.task_ct = ctx.tasks.len();
+    Ok(())
 }
 
 /// Load done.txt file and parse into Task objects.
-/// If the file doesn't exist, create it.
 pub fn get_done(ctx: &mut AppContext) -> Result {
-    read_file_to_string(&ctx.done_file).map(|b| {
-        ctx.task_ct = 0;
-        ctx.done = Tasks(
-            b.lines()
-                .map(|l| {
-                    ctx.task_ct += 1;
-                    Task::new(ctx.task_ct, l)
-                })
-                .collect(),
-        );
+    ctx.done = get_tasks_from_file(&ctx.done_file)?;
+    ctx.done_ct = ctx.done.len();
+    Ok(())
+}
+
+/// Load any todo.txt-formatted file and parse into Task objects
+/// numbered by line.
+pub fn get_tasks_from_file<P>(file_path: P) -> Result<Tasks>
+where
+    P: AsRef<Path> + std::fmt::Debug,
+{
+    read_file_to_string(file_path).map(|b| {
+        b.lines()
+            .enumerate()
+            .map(|(i, l)| Task::new(i + 1, l))
+            .collect()
     })
 }
 
-/// Write tasks to file
+/// Resolve file name relative to the directory containing todo.txt.
+/// Paths that would escape that directory are refused.
+pub fn resolve_in_todo_dir<P>(ctx: &AppContext, file_name: P) -> Result<PathBuf>
+where
+    P: AsRef<Path> + std::fmt::Debug,
+{
+    let todo_dir = ctx
+        .todo_file
+        .parent()
+        .ok_or_else(|| format_err!("todo file {:?} has no parent directory", ctx.todo_file))?;
+    let escapes = file_name
+        .as_ref()
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes || file_name.as_ref().as_os_str().is_empty() {
+        bail!("{:?} is not a file in the todo directory", file_name);
+    }
+    let path = todo_dir.join(&file_name);
+    if let (Ok(dir), Ok(canonical)) = (todo_dir.canonicalize(), path.canonicalize()) {
+        if !canonical.starts_with(dir) {
+            bail!("{:?} resolves outside of the todo directory", file_name);
+        }
+    }
+    Ok(path)
+}
+
+/// Uppercase file stem used to label output for a file, e.g. `SOMEDAY` for
+/// `someday.txt`.
+pub fn get_prefix<P>(file_path: P) -> String
+where
+    P: AsRef<Path>,
+{
+    file_path
+        .as_ref()
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_uppercase())
+        .unwrap_or_default()
+}
+
+/// Write tasks to file.
+///
+/// Appending writes to the end of the existing file. Otherwise the file is
+/// replaced atomically (see [`write_file_atomic`]) so a failed write never
+/// leaves it truncated.
+///
+/// The file is backed up first if backups were enabled for this process by
+/// [`backup::enable`], which sets a global policy rather than passing it here.
+/// Callers that must not take backups, such as tests, leave it unset.
 pub fn write_buf_to_file<T, P>(buf: T, file_path: P, append: bool) -> Result
 where
     T: Into<String>,
     P: AsRef<Path> + std::fmt::Debug,
 {
-    OpenOptions::new()
-        .write(true)
-        .truncate(!append)
-        .append(append)
-        .open(&file_path)
-        .and_then(|mut file| write!(file, "{}{}", buf.into(), if append { "\n" } else { "" }))?;
+    let buf = buf.into();
+    backup::before_write(file_path.as_ref())?;
+    if append {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .and_then(|mut file| {
+                writeln!(file, "{}", buf)?;
+                file.sync_all()
+            })
+            .with_context(|| format!("appending to file {:?}", file_path))?;
+    } else {
+        write_file_atomic(&file_path, |file| file.write_all(buf.as_bytes()))?;
+    }
     info!(
         "{} tasks to file {:?}",
         if append { "Appended" } else { "Wrote" },
@@ -62,6 +117,124 @@ where
     Ok(())
 }
 
+/// Replace contents of file by writing to a temporary file in the same
+/// directory, syncing it to disk and renaming it over the original.
+///
+/// Permissions of the original file are preserved. If any step fails the
+/// temporary file is removed and the original is left untouched.
+pub fn write_file_atomic<P, F>(file_path: P, write: F) -> Result
+where
+    P: AsRef<Path> + std::fmt::Debug,
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    // replace the target of a symlink rather than the link itself
+    let path = fs::canonicalize(&file_path).unwrap_or_else(|_| file_path.as_ref().to_path_buf());
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format_err!("{:?} is not a file path", file_path))?;
+    let tmp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id()
+    ));
+    let result = (|| -> Result {
+        let mut tmp = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+            .with_context(|| format!("creating temporary file {:?}", tmp_path))?;
+        write(&mut tmp).with_context(|| format!("writing temporary file {:?}", tmp_path))?;
+        if let Ok(meta) = fs::metadata(&path) {
+            tmp.set_permissions(meta.permissions())
+                .with_context(|| format!("copying permissions of {:?}", path))?;
+        }
+        tmp.sync_all()
+            .with_context(|| format!("syncing temporary file {:?}", tmp_path))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("renaming {:?} to {:?}", tmp_path, path))?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.with_context(|| format!("writing file {:?}", file_path))?;
+    // make sure the rename itself is on disk
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)
+            .and_then(|d| d.sync_all())
+            .with_context(|| format!("syncing directory {:?}", dir))?;
+    }
+    Ok(())
+}
+
+/// Exclusive advisory lock on a lock file next to the locked file. The lock
+/// is released when dropped, and by the OS if the process dies, so a lock
+/// file left behind doesn't block later commands.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileLock {
+    /// Time to wait between attempts to acquire the lock
+    const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Lock `file_path` by locking `<file_path>.lock`, retrying until
+    /// `timeout` has passed.
+    pub fn acquire<P>(file_path: P, timeout: Duration) -> Result<Self>
+    where
+        P: AsRef<Path> + std::fmt::Debug,
+    {
+        let mut path = file_path.as_ref().as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("opening lock file {:?}", path))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => break,
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => {
+                    if start.elapsed() >= timeout {
+                        let owner = fs::read_to_string(&path).unwrap_or_default();
+                        bail!(
+                            "could not lock {:?} within {}s; lock file {:?} is held by process {}",
+                            file_path,
+                            timeout.as_secs_f32(),
+                            path,
+                            owner.trim(),
+                        );
+                    }
+                    thread::sleep(Self::RETRY_INTERVAL);
+                }
+                Err(e) => return Err(e).with_context(|| format!("locking file {:?}", path)),
+            }
+        }
+        // record owner for the error message of waiting processes
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", process::id()))
+            .with_context(|| format!("writing lock file {:?}", path))?;
+        debug!("Acquired lock {:?}", path);
+        Ok(Self { path, file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        match FileExt::unlock(&self.file) {
+            Ok(_) => debug!("Released lock {:?}", self.path),
+            Err(e) => log::warn!("Error unlocking lock file {:?}: {}", self.path, e),
+        }
+    }
+}
+
 /// Read file to string
 pub fn read_file_to_string<P>(file_path: P) -> Result<String>
 where
@@ -76,3 +249,68 @@ where
         })
         .with_context(|| format!("reading file {:?} to string", file_path))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch_dir;
+    use pretty_assertions::assert_eq;
+
+    /// Names of the files in directory
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut entries = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    /// Rewrite replaces contents and leaves no temporary file behind
+    fn atomic_write_replaces_file() -> Result {
+        let dir = scratch_dir("file-replace");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "old task\n")?;
+        write_buf_to_file("new task\n", &path, false)?;
+        assert_eq!(fs::read_to_string(&path)?, "new task\n");
+        assert_eq!(dir_entries(&dir), vec!["todo.txt"]);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    /// Failure partway through writing keeps the original file intact
+    fn atomic_write_partial_failure() -> Result {
+        let dir = scratch_dir("file-partial");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "old task\n")?;
+        let err = write_file_atomic(&path, |file| {
+            file.write_all(b"new ta")?;
+            Err(io::Error::other("disk full"))
+        })
+        .unwrap_err();
+        let chain = format!("{:#}", err);
+        assert!(chain.contains("writing temporary file"), "{}", chain);
+        assert!(chain.contains("disk full"), "{}", chain);
+        assert_eq!(fs::read_to_string(&path)?, "old task\n");
+        assert_eq!(dir_entries(&dir), vec!["todo.txt"]);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    /// Rewrite keeps the permissions of the original file
+    fn atomic_write_preserves_permissions() -> Result {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = scratch_dir("file-perms");
+        let path = dir.join("todo.txt");
+        fs::write(&path, "old task\n")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        write_buf_to_file("new task\n", &path, false)?;
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
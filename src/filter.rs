@@ -0,0 +1,384 @@
+//! Boolean filter expressions used to select tasks in listing commands
+//!
+//! Terms are combined with AND by default. Other operators are:
+//!
+//! * `a | b`: either term matches (`a\|b` is also accepted)
+//! * `-a`: term does not match
+//! * `( ... )`: grouping
+//!
+//! Besides plain text (a case-insensitive regex matched against the whole
+//! line), these field predicates are available:
+//!
+//! * `+project`, `@context`: task has project or context
+//! * `pri:A`, `pri:A-C`: task has priority in range
+//! * `done:yes`, `done:no`: task is or isn't completed
+//! * `due`, `t`, `created`, `completed` compared with `<`, `<=`, `>`, `>=`,
+//!   `=` (or `:`) or `!=` to a date, e.g. `due<2026-11-01`. Dates relative to
+//!   today are accepted as well (see [`parse_date`]), e.g. `due<=+7d`.
+use crate::{
+    date::{parse_date, today},
+    prelude::*,
+    task::Task,
+};
+use chrono::NaiveDate;
+use regex::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+
+/// Date field of a task that can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateField {
+    Due,
+    Threshold,
+    Created,
+    Completed,
+}
+
+impl DateField {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "due" => Some(Self::Due),
+            "t" | "threshold" => Some(Self::Threshold),
+            "created" => Some(Self::Created),
+            "completed" => Some(Self::Completed),
+            _ => None,
+        }
+    }
+
+    /// Value of this field in the task, if present
+    pub fn get(self, task: &Task) -> Option<NaiveDate> {
+        match self {
+            Self::Due => task.parsed.due_date,
+            Self::Threshold => task.parsed.threshold_date,
+            Self::Created => task.parsed.create_date,
+            Self::Completed => task.parsed.finish_date,
+        }
+    }
+}
+
+/// Comparison operator for date predicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl CmpOp {
+    fn test(self, ord: Ordering) -> bool {
+        match self {
+            Self::Lt => ord == Ordering::Less,
+            Self::Le => ord != Ordering::Greater,
+            Self::Eq => ord == Ordering::Equal,
+            Self::Ne => ord != Ordering::Equal,
+            Self::Ge => ord != Ordering::Less,
+            Self::Gt => ord == Ordering::Greater,
+        }
+    }
+}
+
+/// Compiled filter expression
+#[derive(Debug, Clone)]
+pub enum Filter {
+    /// Matches every task
+    All,
+    Text(Regex),
+    Project(String),
+    Context(String),
+    Priority(char, char),
+    Done(bool),
+    /// Tasks without the date never match
+    Date(DateField, CmpOp, NaiveDate),
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+}
+
+impl Filter {
+    /// Compile filter from command line terms
+    pub fn parse(terms: &[String]) -> Result<Self> {
+        // options after the first term are taken as terms by the argument parser
+        if let Some(option) = terms.iter().find(|t| t.starts_with("--")) {
+            bail!("unexpected option {:?} after TERM; give options before TERM", option);
+        }
+        let tokens = terms.iter().flat_map(|t| tokenize(t)).collect::<Vec<Token>>();
+        if tokens.is_empty() {
+            return Ok(Self::All);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("unexpected {:?} in filter {:?}", token, terms.join(" "));
+        }
+        debug!("Compiled filter: {:?}", filter);
+        Ok(filter)
+    }
+
+    /// Returns true if task matches the filter
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Self::All => true,
+            Self::Text(re) => re.is_match(&task.raw),
+            Self::Project(p) => task.parsed.projects.contains(p),
+            Self::Context(c) => task.parsed.contexts.contains(c),
+            Self::Priority(from, to) => {
+                !task.parsed.priority.is_lowest()
+                    && (*from..=*to).contains(&char::from(task.parsed.priority.clone()))
+            }
+            Self::Done(done) => task.parsed.finished == *done,
+            Self::Date(field, op, date) => field.get(task).is_some_and(|d| op.test(d.cmp(date))),
+            Self::Not(f) => !f.matches(task),
+            Self::And(fs) => fs.iter().all(|f| f.matches(task)),
+            Self::Or(fs) => fs.iter().any(|f| f.matches(task)),
+        }
+    }
+
+    /// Compile a single term into a predicate
+    fn from_term(term: &str) -> Result<Self> {
+        let date_re = Regex::new(r"^(\w+)(<=|>=|!=|<|>|=|:)(.+)$").unwrap();
+        if let Some(project) = term.strip_prefix('+').filter(|p| !p.is_empty()) {
+            return Ok(Self::Project(project.to_lowercase()));
+        }
+        if let Some(context) = term.strip_prefix('@').filter(|c| !c.is_empty()) {
+            return Ok(Self::Context(context.to_lowercase()));
+        }
+        if let Some(pri) = term.strip_prefix("pri:") {
+            let pri = pri.to_ascii_uppercase().chars().collect::<Vec<char>>();
+            return match pri.as_slice() {
+                [p] if p.is_ascii_uppercase() => Ok(Self::Priority(*p, *p)),
+                [from, '-', to] if from.is_ascii_uppercase() && to.is_ascii_uppercase() => {
+                    Ok(Self::Priority(*from, *to))
+                }
+                _ => bail!("invalid priority in filter term {:?}", term),
+            };
+        }
+        if let Some(done) = term.strip_prefix("done:") {
+            return match done.to_ascii_lowercase().as_str() {
+                "yes" | "y" | "true" => Ok(Self::Done(true)),
+                "no" | "n" | "false" => Ok(Self::Done(false)),
+                _ => bail!("expected yes or no in filter term {:?}", term),
+            };
+        }
+        if let Some(caps) = date_re.captures(term) {
+            if let Some(field) = DateField::from_name(&caps[1]) {
+                let op = match &caps[2] {
+                    "<" => CmpOp::Lt,
+                    "<=" => CmpOp::Le,
+                    "=" | ":" => CmpOp::Eq,
+                    "!=" => CmpOp::Ne,
+                    ">=" => CmpOp::Ge,
+                    _ => CmpOp::Gt,
+                };
+                let date = parse_date(&caps[3], today())
+                    .ok_or_else(|| format_err!("invalid date in filter term {:?}", term))?;
+                return Ok(Self::Date(field, op, date));
+            }
+        }
+        let re = RegexBuilder::new(term)
+            .case_insensitive(true)
+            .build()
+            .or_else(|_| {
+                RegexBuilder::new(&regex::escape(term))
+                    .case_insensitive(true)
+                    .build()
+            })?;
+        Ok(Self::Text(re))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    LParen,
+    RParen,
+    Or,
+    Not,
+    Term(String),
+}
+
+/// Split command line argument into tokens. An argument that contains
+/// whitespace but no operators is kept as a single phrase.
+fn tokenize(arg: &str) -> Vec<Token> {
+    let has_ops = arg.contains(['(', ')', '|', '"']);
+    if !has_ops && arg.contains(char::is_whitespace) {
+        return match arg.strip_prefix('-') {
+            Some(rest) if !rest.is_empty() => vec![Token::Not, Token::Term(rest.into())],
+            _ => vec![Token::Term(arg.into())],
+        };
+    }
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut chars = arg.chars().peekable();
+    let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+        if !word.is_empty() {
+            tokens.push(Token::Term(std::mem::take(word)));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' | '|' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    _ => Token::Or,
+                });
+            }
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                flush(&mut word, &mut tokens);
+                tokens.push(Token::Or);
+            }
+            '"' => {
+                flush(&mut word, &mut tokens);
+                let phrase = chars.by_ref().take_while(|&c| c != '"').collect::<String>();
+                if !phrase.is_empty() {
+                    tokens.push(Token::Term(phrase));
+                }
+            }
+            '-' if word.is_empty() && chars.peek().is_some_and(|c| !c.is_whitespace()) => {
+                tokens.push(Token::Not);
+            }
+            c if c.is_whitespace() => flush(&mut word, &mut tokens),
+            c => word.push(c),
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+/// Recursive descent parser over tokens
+struct Parser {
+    tokens: Vec<Token>,
+    pos:    usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// expr := and ('|' and)*
+    fn expr(&mut self) -> Result<Filter> {
+        let mut alts = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            alts.push(self.and()?);
+        }
+        Ok(if alts.len() == 1 {
+            alts.remove(0)
+        } else {
+            Filter::Or(alts)
+        })
+    }
+
+    /// and := unary+
+    fn and(&mut self) -> Result<Filter> {
+        let mut terms = Vec::new();
+        while !matches!(self.peek(), None | Some(Token::Or) | Some(Token::RParen)) {
+            terms.push(self.unary()?);
+        }
+        match terms.len() {
+            0 => bail!("expected filter term"),
+            1 => Ok(terms.remove(0)),
+            _ => Ok(Filter::And(terms)),
+        }
+    }
+
+    /// unary := '-' unary | '(' expr ')' | TERM
+    fn unary(&mut self) -> Result<Filter> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| format_err!("expected filter term"))?;
+        self.pos += 1;
+        match token {
+            Token::Not => Ok(Filter::Not(Box::new(self.unary()?))),
+            Token::LParen => {
+                let filter = self.expr()?;
+                if self.peek() != Some(&Token::RParen) {
+                    bail!("missing ')' in filter");
+                }
+                self.pos += 1;
+                Ok(filter)
+            }
+            Token::Term(term) => Filter::from_term(&term),
+            t => bail!("unexpected {:?} in filter", t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TASKS: &[&str] = &[
+        "(A) Thank Mom for the meatballs @phone",
+        "(B) Schedule Goodwill pickup +GarageSale @phone",
+        "Post signs around the neighborhood +GarageSale",
+        "2019-03-04 Pick up ice @errands due:2019-03-04",
+        "x 2019-01-01 2018-12-01 Call Mom @phone +Family",
+    ];
+
+    /// Ids of test tasks that match filter terms
+    fn matching(terms: &[&str]) -> Vec<usize> {
+        let terms = terms.iter().map(|t| t.to_string()).collect::<Vec<String>>();
+        let filter = Filter::parse(&terms).unwrap();
+        TASKS
+            .iter()
+            .enumerate()
+            .map(|(i, t)| Task::new(i + 1, *t))
+            .filter(|t| filter.matches(t))
+            .map(|t| t.id)
+            .collect()
+    }
+
+    #[test]
+    /// Terms are combined with AND, and can be negated
+    fn filter_and_not() {
+        assert_eq!(matching(&[]), vec![1, 2, 3, 4, 5]);
+        assert_eq!(matching(&["mom", "@phone"]), vec![1, 5]);
+        assert_eq!(matching(&["@phone", "-mom"]), vec![2]);
+        assert_eq!(matching(&["-@phone"]), vec![3, 4]);
+    }
+
+    #[test]
+    /// OR binds looser than AND and can be grouped
+    fn filter_or_groups() {
+        assert_eq!(matching(&["meatballs|ice"]), vec![1, 4]);
+        assert_eq!(matching(&["meatballs\\|ice"]), vec![1, 4]);
+        assert_eq!(matching(&["+garagesale", "pickup", "|", "ice"]), vec![2, 4]);
+        assert_eq!(matching(&["-(@phone | +GarageSale)"]), vec![4]);
+    }
+
+    #[test]
+    /// Field predicates
+    fn filter_fields() {
+        assert_eq!(matching(&["pri:A"]), vec![1]);
+        assert_eq!(matching(&["pri:a-c"]), vec![1, 2]);
+        assert_eq!(matching(&["done:yes"]), vec![5]);
+        assert_eq!(matching(&["due<2019-03-05"]), vec![4]);
+        assert_eq!(matching(&["due>2019-03-04"]), Vec::<usize>::new());
+        assert_eq!(matching(&["created:2018-12-01"]), vec![5]);
+        assert_eq!(matching(&["\"Call Mom\""]), vec![5]);
+        assert_eq!(matching(&["due<today", "created>-30d"]), Vec::<usize>::new());
+        assert_eq!(matching(&["due<today", "-done:yes"]), vec![4]);
+    }
+
+    #[test]
+    /// Malformed expressions are rejected
+    fn filter_errors() {
+        for terms in &[
+            &["(a"][..],
+            &["a)"],
+            &["a", "|"],
+            &["pri:1"],
+            &["due<soon"],
+            &["a", "--format"],
+        ] {
+            let terms = terms.iter().map(|t| t.to_string()).collect::<Vec<String>>();
+            assert!(Filter::parse(&terms).is_err(), "{:?}", terms);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Group tasks into sections by project, context, priority or due date
+use crate::{actions::tags::TagKind, app::GroupBy, task::Task};
+use std::collections::BTreeMap;
+
+/// Tasks sharing a value of the grouped field
+#[derive(Debug)]
+pub struct Group<'a> {
+    /// Value as first written in a task, e.g. `+GarageSale` or `(A)`
+    pub name:     String,
+    /// True for the group of tasks without a value, e.g. `No project`
+    pub is_other: bool,
+    pub tasks:    Vec<&'a Task>,
+}
+
+/// Group tasks by field, in order of value. A task with several projects or
+/// contexts is in each of their groups; tasks without a value are grouped
+/// last. Tasks keep their order within a group.
+pub fn group_tasks(tasks: &[Task], by: GroupBy) -> Vec<Group<'_>> {
+    let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+    let mut other = Group {
+        name:     match by {
+            GroupBy::Project => "No project",
+            GroupBy::Context => "No context",
+            GroupBy::Priority => "No priority",
+            GroupBy::Due => "No due date",
+        }
+        .to_string(),
+        is_other: true,
+        tasks:    Vec::new(),
+    };
+    for task in tasks {
+        // (key, name) of each group the task is in
+        let values = match by {
+            GroupBy::Project | GroupBy::Context => {
+                let kind = if by == GroupBy::Project {
+                    TagKind::Project
+                } else {
+                    TagKind::Context
+                };
+                kind.words(task)
+                    .into_iter()
+                    .map(|w| (w.to_lowercase(), w.to_string()))
+                    .collect()
+            }
+            GroupBy::Priority if task.parsed.priority.is_lowest() => Vec::new(),
+            GroupBy::Priority => {
+                let p = char::from(task.parsed.priority.clone());
+                vec![(p.to_string(), format!("({})", p))]
+            }
+            GroupBy::Due => task
+                .parsed
+                .due_date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .map(|d| (d.clone(), d))
+                .into_iter()
+                .collect::<Vec<(String, String)>>(),
+        };
+        if values.is_empty() {
+            other.tasks.push(task);
+        }
+        for (key, name) in values {
+            let group = groups.entry(key).or_insert_with(|| Group {
+                name,
+                is_other: false,
+                tasks: Vec::new(),
+            });
+            // tags differing only in case share a group
+            if !group.tasks.last().is_some_and(|t| std::ptr::eq(*t, task)) {
+                group.tasks.push(task);
+            }
+        }
+    }
+    let mut groups = groups.into_values().collect::<Vec<Group>>();
+    if !other.tasks.is_empty() {
+        groups.push(other);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Names and task ids of groups
+    fn ids(groups: &[Group]) -> Vec<(String, Vec<usize>)> {
+        groups
+            .iter()
+            .map(|g| (g.name.clone(), g.tasks.iter().map(|t| t.id).collect()))
+            .collect()
+    }
+
+    #[test]
+    /// Tasks with several projects are in each group; others are last
+    fn group_by_fields() {
+        let tasks = vec![
+            Task::new(1, "(B) Sell car +Garage +money due:2026-11-01"),
+            Task::new(2, "Call bank @phone +Money +money +Money"),
+            Task::new(3, "(A) Relax"),
+        ];
+        assert_eq!(ids(&group_tasks(&tasks, GroupBy::Project)), vec![
+            ("+Garage".to_string(), vec![1]),
+            ("+money".to_string(), vec![1, 2]),
+            ("No project".to_string(), vec![3]),
+        ]);
+        assert_eq!(ids(&group_tasks(&tasks, GroupBy::Priority)), vec![
+            ("(A)".to_string(), vec![3]),
+            ("(B)".to_string(), vec![1]),
+            ("No priority".to_string(), vec![2]),
+        ]);
+        assert_eq!(ids(&group_tasks(&tasks, GroupBy::Due)), vec![
+            ("2026-11-01".to_string(), vec![1]),
+            ("No due date".to_string(), vec![2, 3]),
+        ]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Write tasks as iCalendar (RFC 5545) components
+use crate::{actions::tags::TagKind, prelude::*, task::Task};
+use chrono::{Duration, NaiveDate, Utc};
+
+/// Longest content line in octets, excluding the line break
+const MAX_LINE: usize = 75;
+
+/// Write a calendar with one component per task that has a due date.
+///
+/// Tasks become VTODOs, or all-day VEVENTs if `events` is true. A threshold
+/// date is used as the start date.
+pub fn write_calendar<W>(buf: &mut W, tasks: &[Task], events: bool) -> Result
+where
+    W: std::io::Write,
+{
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let tasks = tasks
+        .iter()
+        .filter(|t| t.parsed.due_date.is_some())
+        .collect::<Vec<&Task>>();
+    write_line(buf, "BEGIN:VCALENDAR")?;
+    write_line(buf, "VERSION:2.0")?;
+    write_line(
+        buf,
+        &format!("PRODID:-//todors//todors {}//EN", env!("CARGO_PKG_VERSION")),
+    )?;
+    write_line(buf, "CALSCALE:GREGORIAN")?;
+    for task in tasks {
+        let due = match task.parsed.due_date {
+            Some(d) => d,
+            None => continue,
+        };
+        let mut lines = vec![
+            format!("BEGIN:{}", if events { "VEVENT" } else { "VTODO" }),
+            format!("UID:{}@todors", uid(task)),
+            format!("DTSTAMP:{}", stamp),
+            format!("SUMMARY:{}", escape(&task.parsed.subject)),
+        ];
+        let start = task.parsed.threshold_date.filter(|&t| t <= due);
+        if events {
+            lines.push(format!(
+                "DTSTART;VALUE=DATE:{}",
+                ical_date(start.unwrap_or(due))
+            ));
+            lines.push(format!(
+                "DTEND;VALUE=DATE:{}",
+                ical_date(due + Duration::days(1))
+            ));
+        } else {
+            if let Some(start) = start {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", ical_date(start)));
+            }
+            lines.push(format!("DUE;VALUE=DATE:{}", ical_date(due)));
+        }
+        if let Some(p) = priority(task) {
+            lines.push(format!("PRIORITY:{}", p));
+        }
+        if !events {
+            if task.parsed.finished {
+                lines.push("STATUS:COMPLETED".into());
+                if let Some(d) = task.parsed.finish_date {
+                    lines.push(format!("COMPLETED:{}T000000Z", ical_date(d)));
+                }
+            } else {
+                lines.push("STATUS:NEEDS-ACTION".into());
+            }
+        }
+        let categories = TagKind::Project
+            .words(task)
+            .into_iter()
+            .chain(TagKind::Context.words(task))
+            .map(|w| escape(&w[1..]))
+            .collect::<Vec<String>>();
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        lines.push(format!("END:{}", if events { "VEVENT" } else { "VTODO" }));
+        for line in lines {
+            write_line(buf, &line)?;
+        }
+    }
+    write_line(buf, "END:VCALENDAR")?;
+    Ok(())
+}
+
+/// Identifier that stays the same when the task is reprioritized or
+/// completed: a hash of its creation date and subject. It depends on nothing
+/// else, so tasks sharing both also share a UID.
+fn uid(task: &Task) -> String {
+    let key = format!(
+        "{} {}",
+        task.parsed
+            .create_date
+            .map(|d| d.to_string())
+            .unwrap_or_default(),
+        task.parsed.subject
+    );
+    hash(&key)
+}
+
+/// 64-bit FNV-1a hash in hex, which unlike std's hasher is stable across
+/// releases
+fn hash(s: &str) -> String {
+    let hash = s.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// iCalendar priority: A-H map to 1-8 and the remaining letters to 9
+fn priority(task: &Task) -> Option<u8> {
+    if task.parsed.priority.is_lowest() {
+        return None;
+    }
+    Some((u8::from(task.parsed.priority.clone()) + 1).min(9))
+}
+
+/// Date in `YYYYMMDD` form
+fn ical_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Escape TEXT value
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Write content line terminated by CRLF, folded so no line is longer
+/// than 75 octets. Lines are never split inside a UTF-8 character.
+fn write_line<W>(buf: &mut W, line: &str) -> Result
+where
+    W: std::io::Write,
+{
+    let mut width = 0;
+    for c in line.chars() {
+        // continuation lines start with a space, which counts toward the limit
+        if width + c.len_utf8() > MAX_LINE {
+            write!(buf, "\r\n ")?;
+            width = 1;
+        }
+        write!(buf, "{}", c)?;
+        width += c.len_utf8();
+    }
+    write!(buf, "\r\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    /// Long lines are folded at 75 octets without splitting characters
+    fn ics_fold_and_escape() -> Result {
+        assert_eq!(escape("a, b; c\\d"), "a\\, b\\; c\\\\d");
+        let mut buf = Vec::new();
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        write_line(&mut buf, &line)?;
+        let text = String::from_utf8(buf)?;
+        let lines = text.split("\r\n").collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE));
+        assert_eq!(text.replace("\r\n ", ""), format!("{}\r\n", line));
+        Ok(())
+    }
+
+    #[test]
+    /// Task with due date becomes a VTODO with the same UID after completion
+    fn ics_vtodo() -> Result {
+        let open = Task::new(
+            1,
+            "(A) 2026-10-01 Pay rent, bills +Home @bank due:2026-10-20 t:2026-10-15",
+        );
+        let done = Task::new(
+            1,
+            "x 2026-10-16 2026-10-01 Pay rent, bills +Home @bank due:2026-10-20 t:2026-10-15",
+        );
+        let no_due = Task::new(2, "Someday");
+        let calendar = |tasks: &[Task]| -> Result<String> {
+            let mut buf = Vec::new();
+            write_calendar(&mut buf, tasks, false)?;
+            Ok(String::from_utf8(buf)?)
+        };
+        let id = uid(&open);
+        assert_eq!(id, uid(&done));
+        assert!(calendar(std::slice::from_ref(&open))?.contains(&format!("UID:{}@todors\r\n", id)));
+        let text = calendar(&[open, no_due, done])?;
+        assert_eq!(text.matches("BEGIN:VTODO").count(), 2);
+        for line in &[
+            &format!("UID:{}@todors", id),
+            "SUMMARY:Pay rent\\, bills +Home @bank",
+            "DTSTART;VALUE=DATE:20261015",
+            "DUE;VALUE=DATE:20261020",
+            "PRIORITY:1",
+            "STATUS:NEEDS-ACTION",
+            "STATUS:COMPLETED",
+            "COMPLETED:20261016T000000Z",
+            "CATEGORIES:Home,bank",
+        ] {
+            assert!(text.contains(&format!("{}\r\n", line)), "missing {}", line);
+        }
+        Ok(())
+    }
+
+    #[test]
+    /// A task keeps its UID when other tasks with the same subject are added
+    fn ics_uid_stable() -> Result {
+        let a = Task::new(1, "(B) Water plants due:2026-10-20");
+        let b = Task::new(2, "Water plants due:2026-10-27");
+        let uids = |tasks: &[Task]| -> Result<Vec<String>> {
+            let mut buf = Vec::new();
+            write_calendar(&mut buf, tasks, false)?;
+            Ok(String::from_utf8(buf)?
+                .lines()
+                .filter(|l| l.starts_with("UID:"))
+                .map(String::from)
+                .collect())
+        };
+        let alone = uids(std::slice::from_ref(&a))?;
+        assert_eq!(alone, vec![format!("UID:{}@todors", uid(&a))]);
+        assert_eq!(uids(&[a.clone(), b.clone()])?[0], alone[0]);
+        assert_eq!(uids(&[b, a.clone()])?[1], alone[0]);
+        assert_eq!(uids(&[a.clone(), a])?[1], alone[0]);
+        Ok(())
+    }
+}
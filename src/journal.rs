@@ -0,0 +1,300 @@
+//! Journal of changes made to files so they can be undone and redone
+use crate::{
+    config::AppContext,
+    file::{read_file_to_string, write_buf_to_file},
+    prelude::*,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Number of entries kept in the journal
+const MAX_ENTRIES: usize = 100;
+
+/// Contiguous block of lines in a file that was replaced by a command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    pub file:   PathBuf,
+    /// Index of first changed line
+    pub line:   usize,
+    pub before: Vec<String>,
+    pub after:  Vec<String>,
+}
+
+impl Change {
+    /// Compare file contents before and after a command. Returns `None` if
+    /// nothing changed.
+    fn diff(file: &Path, before: &str, after: &str) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+        let before = before.split('\n').collect::<Vec<&str>>();
+        let after = after.split('\n').collect::<Vec<&str>>();
+        let prefix = before
+            .iter()
+            .zip(&after)
+            .take_while(|(b, a)| b == a)
+            .count();
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(b, a)| b == a)
+            .count();
+        let to_vec = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect();
+        Some(Self {
+            file:   file.to_path_buf(),
+            line:   prefix,
+            before: to_vec(&before[prefix..before.len() - suffix]),
+            after:  to_vec(&after[prefix..after.len() - suffix]),
+        })
+    }
+
+    /// Contents of the file with `from` lines replaced by `to` lines,
+    /// checking that the file still contains `from` at the recorded position.
+    fn replaced(&self, from: &[String], to: &[String]) -> Result<String> {
+        let contents = read_file_to_string(&self.file)?;
+        let mut lines = contents.split('\n').collect::<Vec<&str>>();
+        let end = self.line + from.len();
+        if end > lines.len() || lines[self.line..end] != *from {
+            bail!(
+                "{:?} has changed since the journal entry was recorded",
+                self.file
+            );
+        }
+        lines.splice(self.line..end, to.iter().map(String::as_str));
+        Ok(lines.join("\n"))
+    }
+}
+
+/// All changes made to files by a single command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: String,
+    pub command:   String,
+    pub changes:   Vec<Change>,
+}
+
+impl Entry {
+    /// Revert (if `undo`) or reapply the changes. Every file is checked
+    /// before any is written, so either all files change or none do.
+    fn apply(&self, undo: bool) -> Result {
+        let contents = self
+            .changes
+            .iter()
+            .map(|c| {
+                let (from, to) = if undo {
+                    (&c.after, &c.before)
+                } else {
+                    (&c.before, &c.after)
+                };
+                c.replaced(from, to).map(|s| (&c.file, s))
+            })
+            .collect::<Result<Vec<(&PathBuf, String)>>>()?;
+        for (file, contents) in contents {
+            write_buf_to_file(contents, file, false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Entries in order of execution. Entries before `position` are applied;
+/// the rest have been undone and can be redone.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Journal {
+    pub entries:  Vec<Entry>,
+    pub position: usize,
+}
+
+/// Contents of files before a command was run
+#[derive(Debug)]
+pub struct Snapshot(Vec<(PathBuf, String)>);
+
+impl Snapshot {
+    /// Read current contents of files. Missing files are treated as empty.
+    pub fn take(files: &[PathBuf]) -> Self {
+        Self(
+            files
+                .iter()
+                .map(|f| (f.clone(), read_file_to_string(f).unwrap_or_default()))
+                .collect(),
+        )
+    }
+
+    /// Changes made to the files since the snapshot was taken
+    fn changes(&self) -> Vec<Change> {
+        self.0
+            .iter()
+            .filter_map(|(file, before)| {
+                let after = read_file_to_string(file).unwrap_or_default();
+                Change::diff(file, before, &after)
+            })
+            .collect()
+    }
+}
+
+impl Journal {
+    /// Location of journal file for todo.txt
+    pub fn path(ctx: &AppContext) -> PathBuf {
+        let mut path = ctx.todo_file.as_os_str().to_owned();
+        path.push(".journal");
+        PathBuf::from(path)
+    }
+
+    /// Load journal, or an empty journal if none exists yet
+    pub fn load(ctx: &AppContext) -> Result<Self> {
+        let path = Self::path(ctx);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(&read_file_to_string(&path)?)
+            .with_context(|| format!("parsing journal {:?}", path))
+    }
+
+    /// Save journal next to todo.txt
+    pub fn save(&self, ctx: &AppContext) -> Result {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_buf_to_file(contents, Self::path(ctx), false)
+    }
+
+    /// Record changes made since `snapshot` as a new entry. Entries that were
+    /// undone can no longer be redone afterward.
+    pub fn record(ctx: &AppContext, snapshot: &Snapshot, command: String) -> Result {
+        let changes = snapshot.changes();
+        if changes.is_empty() {
+            return Ok(());
+        }
+        let mut journal = Self::load(ctx)?;
+        journal.entries.truncate(journal.position);
+        journal.entries.push(Entry {
+            timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            command,
+            changes,
+        });
+        let excess = journal.entries.len().saturating_sub(MAX_ENTRIES);
+        journal.entries.drain(..excess);
+        journal.position = journal.entries.len();
+        debug!("Recorded journal entry {}", journal.position);
+        journal.save(ctx)
+    }
+}
+
+/// Revert the last `steps` applied entries
+pub fn undo(steps: usize, ctx: &AppContext) -> Result<bool> {
+    let mut journal = Journal::load(ctx)?;
+    if journal.position == 0 {
+        println!("TODO: Nothing to undo.");
+        return Ok(false);
+    }
+    for _ in 0..steps.min(journal.position) {
+        let entry = &journal.entries[journal.position - 1];
+        entry.apply(true)?;
+        println!("TODO: Undid '{}' from {}.", entry.command, entry.timestamp);
+        journal.position -= 1;
+        journal.save(ctx)?;
+    }
+    Ok(true)
+}
+
+/// Reapply the next `steps` undone entries
+pub fn redo(steps: usize, ctx: &AppContext) -> Result<bool> {
+    let mut journal = Journal::load(ctx)?;
+    if journal.position == journal.entries.len() {
+        println!("TODO: Nothing to redo.");
+        return Ok(false);
+    }
+    for _ in 0..steps.min(journal.entries.len() - journal.position) {
+        let entry = &journal.entries[journal.position];
+        entry.apply(false)?;
+        println!("TODO: Redid '{}' from {}.", entry.command, entry.timestamp);
+        journal.position += 1;
+        journal.save(ctx)?;
+    }
+    Ok(true)
+}
+
+/// Print journal entries, most recent first. Undone entries are marked.
+pub fn list<W>(buf: &mut W, ctx: &AppContext) -> Result
+where
+    W: std::io::Write + termcolor::WriteColor,
+{
+    let journal = Journal::load(ctx)?;
+    for (i, entry) in journal.entries.iter().enumerate().rev() {
+        // number of undo steps needed to revert this entry
+        let steps = journal.position.saturating_sub(i);
+        let mark = if i < journal.position {
+            steps.to_string()
+        } else {
+            String::from("(undone)")
+        };
+        writeln!(buf, "{:>8}  {}  {}", mark, entry.timestamp, entry.command)?;
+        for change in &entry.changes {
+            let file = change.file.file_name().unwrap_or_default();
+            writeln!(buf, "          {}:", file.to_string_lossy())?;
+            for line in &change.before {
+                writeln!(buf, "          - {}", line)?;
+            }
+            for line in &change.after {
+                writeln!(buf, "          + {}", line)?;
+            }
+        }
+    }
+    writeln!(
+        buf,
+        "--\nJOURNAL: {} of {} changes applied",
+        journal.position,
+        journal.entries.len()
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    /// Diff covers only the changed block of lines
+    fn diff_changed_lines() {
+        let change = Change::diff(Path::new("todo.txt"), "a\nb\nc\nd\n", "a\nB\nx\nd\n").unwrap();
+        assert_eq!(change.line, 1);
+        assert_eq!(change.before, vec!["b", "c"]);
+        assert_eq!(change.after, vec!["B", "x"]);
+        assert_eq!(Change::diff(Path::new("todo.txt"), "a\n", "a\n"), None);
+    }
+
+    #[test]
+    /// Appended line is recorded as an insertion
+    fn diff_appended_line() {
+        let change = Change::diff(Path::new("todo.txt"), "a\nb\n", "a\nb\nc\n").unwrap();
+        assert_eq!(change.line, 2);
+        assert!(change.before.is_empty());
+        assert_eq!(change.after, vec!["c"]);
+    }
+
+    #[test]
+    /// No file is written if any file of an entry has changed since
+    fn apply_all_or_nothing() -> Result {
+        let dir = crate::util::scratch_dir("journal-apply");
+        let (todo, done) = (dir.join("todo.txt"), dir.join("done.txt"));
+        std::fs::write(&todo, "a\n")?;
+        std::fs::write(&done, "x b\n")?;
+        let entry = Entry {
+            timestamp: String::new(),
+            command:   "do 2".into(),
+            changes:   vec![
+                Change::diff(&todo, "a\nb\n", "a\n").unwrap(),
+                Change::diff(&done, "", "x b\n").unwrap(),
+            ],
+        };
+        std::fs::write(&done, "x b edited\n")?;
+        assert!(entry.apply(true).is_err());
+        assert_eq!(read_file_to_string(&todo)?, "a\n");
+        std::fs::write(&done, "x b\n")?;
+        entry.apply(true)?;
+        assert_eq!(read_file_to_string(&todo)?, "a\nb\n");
+        assert_eq!(read_file_to_string(&done)?, "");
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
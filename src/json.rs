@@ -0,0 +1,107 @@
+//! Machine-readable output of listing commands
+use crate::{app::ListFormat, prelude::*, task::Task};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Task as serialized to JSON
+#[derive(Debug, Serialize)]
+pub struct JsonTask<'a> {
+    pub id:             usize,
+    pub raw:            &'a str,
+    pub finished:       bool,
+    pub priority:       Option<char>,
+    pub create_date:    Option<String>,
+    pub finish_date:    Option<String>,
+    pub due_date:       Option<String>,
+    pub threshold_date: Option<String>,
+    pub projects:       &'a [String],
+    pub contexts:       &'a [String],
+    pub hashtags:       &'a [String],
+    pub tags:           &'a BTreeMap<String, String>,
+}
+
+impl<'a> From<&'a Task> for JsonTask<'a> {
+    fn from(task: &'a Task) -> Self {
+        let p = &task.parsed;
+        let date = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+        JsonTask {
+            id:             task.id,
+            raw:            &task.raw,
+            finished:       p.finished,
+            priority:       (!p.priority.is_lowest()).then(|| char::from(p.priority.clone())),
+            create_date:    date(p.create_date),
+            finish_date:    date(p.finish_date),
+            due_date:       date(p.due_date),
+            threshold_date: date(p.threshold_date),
+            projects:       &p.projects,
+            contexts:       &p.contexts,
+            hashtags:       &p.hashtags,
+            tags:           &p.tags,
+        }
+    }
+}
+
+/// Counts shown in the footer of a task listing
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    /// File label, e.g. `TODO`
+    pub label:      String,
+    pub shown:      usize,
+    pub total:      usize,
+    /// Counts for done.txt, only set by `listall`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_shown: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done_total: Option<usize>,
+    /// Open tasks hidden by a future threshold date
+    pub deferred:   usize,
+}
+
+/// Tag with number of open and done tasks using it
+#[derive(Debug, Serialize)]
+pub struct JsonTag<'a> {
+    pub tag:  &'a str,
+    pub open: usize,
+    pub done: usize,
+}
+
+/// Write `items` and `summary` under keys `key` and `summary`.
+///
+/// `json` writes a single object; `jsonl` writes one item per line followed
+/// by a line with the summary object.
+pub fn write_json<W, T, S>(
+    buf: &mut W,
+    format: ListFormat,
+    key: &str,
+    items: &[T],
+    summary: &S,
+) -> Result
+where
+    W: std::io::Write,
+    T: Serialize,
+    S: Serialize,
+{
+    match format {
+        ListFormat::Json => {
+            let mut ser = serde_json::Serializer::pretty(&mut *buf);
+            let mut map = ser.serialize_map(Some(2))?;
+            map.serialize_entry(key, items)?;
+            map.serialize_entry("summary", summary)?;
+            map.end()?;
+            writeln!(buf)?;
+        }
+        ListFormat::Jsonl => {
+            for item in items {
+                serde_json::to_writer(&mut *buf, item)?;
+                writeln!(buf)?;
+            }
+            let mut ser = serde_json::Serializer::new(&mut *buf);
+            let mut map = ser.serialize_map(Some(1))?;
+            map.serialize_entry("summary", summary)?;
+            map.end()?;
+            writeln!(buf)?;
+        }
+        ListFormat::Text => bail!("text is not a JSON format"),
+    }
+    Ok(())
+}
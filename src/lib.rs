@@ -24,10 +24,20 @@
 #![allow(clippy::pedantic)]
 pub mod actions;
 pub mod app;
+pub mod backup;
 // pub mod color;
 pub mod config;
+pub mod csv;
+pub mod date;
 pub mod file;
+pub mod filter;
+pub mod group;
+pub mod ics;
+pub mod journal;
+pub mod json;
+pub mod markup;
 pub mod prelude;
 pub mod style;
 pub mod task;
+pub mod template;
 pub mod util;
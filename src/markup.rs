@@ -0,0 +1,246 @@
+//! Render tasks as Markdown or HTML reports grouped by project
+use crate::{
+    actions::tags::TagKind,
+    app::GroupBy,
+    config::AppContext,
+    date::today,
+    group::{group_tasks, Group},
+    prelude::*,
+    style::get_css_from_style,
+    task::Task,
+    util::get_pri_name,
+};
+/// Heading of group with open and done counts
+fn heading(group: &Group) -> String {
+    let done = group.tasks.iter().filter(|t| t.parsed.finished).count();
+    format!(
+        "{} ({} open, {} done)",
+        group.name,
+        group.tasks.len() - done,
+        done
+    )
+}
+
+/// Group tasks by project. Within a group, open tasks come first, then by
+/// priority and line number.
+fn group_by_project(tasks: &[Task]) -> Vec<Group<'_>> {
+    let mut groups = group_tasks(tasks, GroupBy::Project);
+    for group in &mut groups {
+        group.tasks.sort_by_key(|t| {
+            (
+                t.parsed.finished,
+                u8::from(t.parsed.priority.clone()),
+                t.id,
+            )
+        });
+    }
+    groups
+}
+
+/// Summary line with counts of open and done tasks and projects
+fn summary(tasks: &[Task], groups: &[Group]) -> String {
+    let done = tasks.iter().filter(|t| t.parsed.finished).count();
+    let projects = groups.iter().filter(|g| !g.is_other).count();
+    format!(
+        "{} open, {} done in {} project(s) as of {}",
+        tasks.len() - done,
+        done,
+        projects,
+        today().format("%Y-%m-%d")
+    )
+}
+
+/// Priority letter of an unfinished or finished task
+fn priority(task: &Task) -> Option<char> {
+    if task.parsed.priority.is_lowest() {
+        return None;
+    }
+    Some(char::from(task.parsed.priority.clone()))
+}
+
+/// Write tasks as a Markdown task list under a heading for each project
+pub fn write_markdown<W>(buf: &mut W, tasks: &[Task]) -> Result
+where
+    W: std::io::Write,
+{
+    let groups = group_by_project(tasks);
+    writeln!(buf, "# Tasks\n\n{}", summary(tasks, &groups))?;
+    for group in &groups {
+        writeln!(buf, "\n## {}\n", escape_markdown(&heading(group)))?;
+        for task in &group.tasks {
+            let check = if task.parsed.finished { 'x' } else { ' ' };
+            write!(buf, "- [{}] ", check)?;
+            if let Some(p) = priority(task) {
+                write!(buf, "**({})** ", p)?;
+            }
+            write!(buf, "{}", escape_markdown(&task.parsed.subject))?;
+            if let Some(due) = task.parsed.due_date {
+                write!(buf, " _(due {})_", due.format("%Y-%m-%d"))?;
+            }
+            writeln!(buf)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write tasks as a self-contained HTML page with a list for each project.
+/// Priorities, projects and contexts are colored with the configured styles.
+pub fn write_html<W>(buf: &mut W, tasks: &[Task], ctx: &AppContext) -> Result
+where
+    W: std::io::Write,
+{
+    let groups = group_by_project(tasks);
+    let mut pri_names = tasks
+        .iter()
+        .filter_map(|t| get_pri_name(u8::from(t.parsed.priority.clone())))
+        .collect::<Vec<String>>();
+    pri_names.sort();
+    pri_names.dedup();
+    let mut styles = vec!["done".to_string(), "project".into(), "context".into()];
+    styles.extend(pri_names);
+    writeln!(
+        buf,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Tasks</title>\n<style>"
+    )?;
+    writeln!(
+        buf,
+        "body {{ font-family: sans-serif; max-width: 50em; margin: 2em auto; }}\n\
+         ul {{ list-style: none; padding-left: 0; }}\n\
+         li {{ margin: 0.2em 0; }}\n\
+         .badge {{ border: 1px solid currentColor; border-radius: 0.3em; \
+         padding: 0 0.3em; font-size: 85%; }}\n\
+         .due {{ color: #808080; }}\n\
+         li.done .subject {{ text-decoration: line-through; }}"
+    )?;
+    for name in &styles {
+        let css = get_css_from_style(name, ctx);
+        if !css.is_empty() {
+            writeln!(buf, ".{} {{ {}; }}", name, css)?;
+        }
+    }
+    writeln!(buf, "</style>\n</head>\n<body>\n<h1>Tasks</h1>")?;
+    writeln!(
+        buf,
+        "<p class=\"summary\">{}</p>",
+        escape_html(&summary(tasks, &groups))
+    )?;
+    for group in &groups {
+        writeln!(buf, "<h2>{}</h2>\n<ul>", escape_html(&heading(group)))?;
+        for task in &group.tasks {
+            let (class, checked) = if task.parsed.finished {
+                (" class=\"done\"", " checked")
+            } else {
+                ("", "")
+            };
+            write!(
+                buf,
+                "<li{}><input type=\"checkbox\" disabled{}> ",
+                class, checked
+            )?;
+            if let Some(p) = priority(task) {
+                let name = get_pri_name(u8::from(task.parsed.priority.clone())).unwrap_or_default();
+                write!(buf, "<span class=\"badge {}\">{}</span> ", name, p)?;
+            }
+            write!(buf, "<span class=\"subject\">{}</span>", html_words(task))?;
+            if let Some(due) = task.parsed.due_date {
+                write!(buf, " <span class=\"due\">due {}</span>", due.format("%Y-%m-%d"))?;
+            }
+            writeln!(buf, "</li>")?;
+        }
+        writeln!(buf, "</ul>")?;
+    }
+    writeln!(buf, "</body>\n</html>")?;
+    Ok(())
+}
+
+/// Subject of task as HTML with projects and contexts wrapped in spans
+fn html_words(task: &Task) -> String {
+    let projects = TagKind::Project.words(task);
+    let contexts = TagKind::Context.words(task);
+    task.parsed
+        .subject
+        .split_whitespace()
+        .map(|w| {
+            if projects.contains(&w) {
+                format!("<span class=\"project\">{}</span>", escape_html(w))
+            } else if contexts.contains(&w) {
+                format!("<span class=\"context\">{}</span>", escape_html(w))
+            } else {
+                escape_html(w)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Escape characters with special meaning in HTML
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape characters with special meaning in Markdown
+fn escape_markdown(s: &str) -> String {
+    s.chars().fold(String::new(), |mut out, c| {
+        if "\\`*_[]<>#|".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn tasks() -> Vec<Task> {
+        vec![
+            Task::new(1, "Post signs +GarageSale"),
+            Task::new(2, "(B) Schedule pickup +GarageSale @phone due:2026-10-20"),
+            Task::new(3, "x 2026-10-16 Call <doctor> +Health"),
+            Task::new(4, "Buy milk_2"),
+        ]
+    }
+
+    #[test]
+    /// Tasks are grouped by project with checkboxes and priority badges
+    fn markdown_by_project() -> Result {
+        let mut buf = Vec::new();
+        write_markdown(&mut buf, &tasks())?;
+        let text = String::from_utf8(buf)?;
+        let body = text.split_once("\n\n## ").map(|(_, b)| b).unwrap_or_default();
+        assert_eq!(
+            body,
+            "+GarageSale (2 open, 0 done)\n\n\
+             - [ ] **(B)** Schedule pickup +GarageSale @phone _(due 2026-10-20)_\n\
+             - [ ] Post signs +GarageSale\n\
+             \n## +Health (0 open, 1 done)\n\n\
+             - [x] Call \\<doctor\\> +Health\n\
+             \n## No project (1 open, 0 done)\n\n\
+             - [ ] Buy milk\\_2\n"
+        );
+        assert!(text.contains("3 open, 1 done in 2 project(s)"));
+        Ok(())
+    }
+
+    #[test]
+    /// HTML uses style names as classes and escapes text
+    fn html_by_project() -> Result {
+        let ctx = AppContext::default();
+        let mut buf = Vec::new();
+        write_html(&mut buf, &tasks(), &ctx)?;
+        let html = String::from_utf8(buf)?;
+        assert!(html.contains(".pri_b { color: #008000; }"));
+        assert!(!html.contains(".pri_a"));
+        assert!(html.contains("<span class=\"badge pri_b\">B</span>"));
+        assert!(html.contains("<span class=\"context\">@phone</span>"));
+        assert!(html.contains("<li class=\"done\"><input type=\"checkbox\" disabled checked>"));
+        assert!(html.contains("Call &lt;doctor&gt;"));
+        Ok(())
+    }
+}
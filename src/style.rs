@@ -1,4 +1,10 @@
-use crate::{config::AppContext, prelude::*, util::get_pri_name};
+use crate::{
+    config::AppContext,
+    prelude::*,
+    task::Task,
+    template::{field_text, field_words, padding, Align, Field, Part, Template, DEFAULT_TEMPLATE},
+    util::get_pri_name,
+};
 use serde::Deserialize;
 
 use termcolor::{Color, ColorSpec};
@@ -37,6 +43,7 @@ impl Style {
             match name {
                 "project" => default.color_fg = Some(Ansi::LIME),
                 "context" => default.color_fg = Some(Ansi::LIGHTORANGE),
+                "heading" => default.bold = Some(true),
                 _ => default.color_fg = None,
             }
             default
@@ -62,15 +69,19 @@ impl Ansi {
     pub const TURQUOISE: u8 = 37;
 }
 
+/// Find item style in preferences, or return `default`
+fn find_style<'a>(name: &str, ctx: &'a AppContext, default: &'a Style) -> &'a Style {
+    ctx.styles
+        .iter()
+        .find(|i| i.name.to_ascii_lowercase() == name)
+        .unwrap_or(default)
+}
+
 /// Get item style from preferences (or default)
 pub fn get_colors_from_style(name: &str, ctx: &AppContext) -> Result<ColorSpec> {
     // TODO: build ColorSpecs for each style in the configuration and iterate once
-    let default_style = Style::default(&name);
-    let style = ctx
-        .styles
-        .iter()
-        .find(|i| i.name.to_ascii_lowercase() == name)
-        .unwrap_or(&default_style);
+    let default_style = Style::default(name);
+    let style = find_style(name, ctx, &default_style);
     let mut color = ColorSpec::new();
     color.set_reset(false);
     if let Some(fg) = style.color_fg {
@@ -85,6 +96,47 @@ pub fn get_colors_from_style(name: &str, ctx: &AppContext) -> Result<ColorSpec>
     Ok(color)
 }
 
+/// Get item style from preferences (or default) as CSS declarations
+pub fn get_css_from_style(name: &str, ctx: &AppContext) -> String {
+    let default_style = Style::default(name);
+    let style = find_style(name, ctx, &default_style);
+    let mut css = Vec::new();
+    if let Some(fg) = style.color_fg {
+        css.push(format!("color: {}", ansi_to_hex(fg)));
+    }
+    if let Some(bg) = style.color_bg {
+        css.push(format!("background-color: {}", ansi_to_hex(bg)));
+    }
+    if style.bold == Some(true) {
+        css.push("font-weight: bold".to_string());
+    }
+    if style.underline == Some(true) {
+        css.push("text-decoration: underline".to_string());
+    }
+    css.join("; ")
+}
+
+/// Convert ANSI 256-color code to CSS hex color using the xterm palette
+pub fn ansi_to_hex(code: u8) -> String {
+    const SYSTEM: [u32; 16] = [
+        0x000000, 0x800000, 0x008000, 0x808000, 0x000080, 0x800080, 0x008080, 0xc0c0c0,
+        0x808080, 0xff0000, 0x00ff00, 0xffff00, 0x0000ff, 0xff00ff, 0x00ffff, 0xffffff,
+    ];
+    let rgb = match u32::from(code) {
+        c @ 0..=15 => SYSTEM[c as usize],
+        c @ 16..=231 => {
+            let level = |n: u32| if n == 0 { 0 } else { 55 + 40 * n };
+            let c = c - 16;
+            level(c / 36) << 16 | level(c / 6 % 6) << 8 | level(c % 6)
+        }
+        c => {
+            let gray = 8 + 10 * (c - 232);
+            gray << 16 | gray << 8 | gray
+        }
+    };
+    format!("#{:06x}", rgb)
+}
+
 // pub fn get_stylespec(name: &str, ctx: &AppContext) -> Result<color::StyleContext> {
 //     let default_style = Style::default(&name);
 //     let style = ctx
@@ -108,15 +160,36 @@ pub fn get_colors_from_style(name: &str, ctx: &AppContext) -> Result<ColorSpec>
 //     Ok(color_style)
 // }
 
-/// Format output and add color to priorities, projects and contexts
+/// Format output and add color to priorities, projects and contexts.
+///
+/// Each line is written with the `--format-template` flag, the `list_format`
+/// setting or the todo.sh layout. The line has the color of its priority;
+/// projects and contexts in placeholders get their own colors. Spaces next to
+/// empty placeholders are dropped.
 pub fn format_buffer<W>(buf: &mut W, ctx: &AppContext) -> Result
 where
     W: std::io::Write + termcolor::WriteColor,
 {
-    // let leading_zeros = max(1, ctx.task_ct.to_string().len());
-    let leading_zeros = ctx.task_ct.to_string().len();
-    for task in &*ctx.tasks {
-        let line = &task.raw;
+    format_tasks(buf, ctx.tasks.iter(), ctx)
+}
+
+/// Format `tasks` like `format_buffer`
+pub fn format_tasks<'a, W, I>(buf: &mut W, tasks: I, ctx: &AppContext) -> Result
+where
+    W: std::io::Write + termcolor::WriteColor,
+    I: IntoIterator<Item = &'a Task>,
+{
+    let template = ctx
+        .opts
+        .format_template
+        .as_deref()
+        .or(ctx.settings.list_format.as_deref())
+        .unwrap_or(DEFAULT_TEMPLATE);
+    let template = Template::parse(template)?;
+    let hide_project = ctx.opts.hide_project % 2 == 1;
+    let hide_context = ctx.opts.hide_context % 2 == 1;
+    let hide_priority = ctx.opts.hide_priority % 2 == 1;
+    for task in tasks {
         let pri = get_pri_name(u8::from(task.parsed.priority.clone())).unwrap_or_default();
         let color = if task.parsed.finished {
             get_colors_from_style("done", ctx)?
@@ -124,37 +197,81 @@ where
             get_colors_from_style(&pri, ctx)?
         };
         buf.set_color(&color)?;
-        // write line number
-        // TODO: why is this leaving out leading zero suddenly?
-        write!(buf, "{:0width$} ", task.id, width = leading_zeros)?;
-        let mut words = line.split_whitespace().peekable();
-        while let Some(word) = words.next() {
-            let first_char = word.chars().next();
-            let prev_color = color.clone();
-            match first_char {
-                Some('+') => {
-                    if ctx.opts.hide_project % 2 == 0 {
-                        buf.set_color(&get_colors_from_style("project", ctx)?)?;
-                        write!(buf, "{}", word)?;
-                        buf.reset()?;
-                        buf.set_color(&prev_color)?;
+        // words of each placeholder, empty for literals
+        let words = template
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(_) => Vec::new(),
+                Part::Placeholder { field, .. } if field.has_tags() => {
+                    field_words(task, *field, hide_project, hide_context)
+                }
+                Part::Placeholder { field: Field::Pri, .. } if hide_priority => Vec::new(),
+                Part::Placeholder { field, pad, date } => {
+                    let task_ct = if pad.is_some() { 0 } else { ctx.task_ct };
+                    let text = field_text(task, *field, date, task_ct);
+                    if text.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![text]
+                    }
+                }
+            })
+            .collect::<Vec<Vec<String>>>();
+        let is_empty = |i: usize| match &template.parts[i] {
+            Part::Literal(s) => s.trim().is_empty(),
+            Part::Placeholder { pad, .. } => pad.is_none() && words[i].is_empty(),
+        };
+        let mut skip_space = false;
+        for (i, part) in template.parts.iter().enumerate() {
+            let (field, pad) = match part {
+                Part::Literal(s) => {
+                    let mut s = s.as_str();
+                    if skip_space {
+                        s = s.strip_prefix(' ').unwrap_or(s);
+                    }
+                    if i + 1 < template.parts.len() && (i + 1..template.parts.len()).all(is_empty) {
+                        s = s.trim_end();
                     }
+                    write!(buf, "{}", s)?;
+                    skip_space = false;
+                    continue;
                 }
-                Some('@') => {
-                    if ctx.opts.hide_context % 2 == 0 {
-                        buf.set_color(&get_colors_from_style("context", ctx)?)?;
+                Part::Placeholder { field, pad, .. } => (field, pad),
+            };
+            if is_empty(i) {
+                skip_space = true;
+                continue;
+            }
+            skip_space = false;
+            let len = words[i].iter().map(|w| w.chars().count() + 1).sum::<usize>();
+            let (before, after) = padding(len.saturating_sub(1), *pad);
+            let fill = match pad {
+                Some((Align::Right, _, true)) => "0",
+                _ => " ",
+            };
+            write!(buf, "{}", fill.repeat(before))?;
+            let mut words = words[i].iter().peekable();
+            while let Some(word) = words.next() {
+                let tag_style = match word.chars().next() {
+                    Some('+') if field.has_tags() => Some("project"),
+                    Some('@') if field.has_tags() => Some("context"),
+                    _ => None,
+                };
+                match tag_style {
+                    Some(name) => {
+                        buf.set_color(&get_colors_from_style(name, ctx)?)?;
                         write!(buf, "{}", word)?;
                         buf.reset()?;
-                        buf.set_color(&prev_color)?;
+                        buf.set_color(&color)?;
                     }
+                    None => write!(buf, "{}", word)?,
                 }
-                _ => {
-                    write!(buf, "{}", word)?;
+                if words.peek().is_some() {
+                    write!(buf, " ")?;
                 }
             }
-            if words.peek().is_some() {
-                write!(buf, " ")?;
-            }
+            write!(buf, "{}", " ".repeat(after))?;
         }
         if !task.parsed.priority.is_lowest() || task.parsed.finished {
             buf.reset()?;
@@ -1,7 +1,8 @@
 //! Module containing Task objects and the Tasks container
 
-use crate::{config::AppContext, prelude::*};
-use regex::RegexSetBuilder;
+use crate::{config::AppContext, filter::Filter, prelude::*};
+use chrono::NaiveDate;
+use serde::Deserialize;
 use std::{
     cmp::Ordering,
     fmt::{self, Display},
@@ -59,7 +60,7 @@ impl Add for Tasks {
     type Output = Self;
 
     fn add(self, other: Self) -> Self::Output {
-        let mut c = Vec::from(self.0);
+        let mut c = self.0;
         for i in other {
             c.push(i);
         }
@@ -91,6 +92,16 @@ impl Tasks {
         self
     }
 
+    /// Get mutable reference to a non-blank Task by id
+    pub fn get_by_id_mut(&mut self, id: usize) -> Option<&mut Task> {
+        self.0.iter_mut().find(|t| t.id == id && !t.is_blank())
+    }
+
+    /// Raw lines of all tasks, each terminated by a newline
+    pub fn to_raw_string(&self) -> String {
+        self.0.iter().map(|t| format!("{}\n", t.raw)).collect()
+    }
+
     /// Returns the number of elements in the slice
     pub fn len(&self) -> usize {
         self.0.len()
@@ -121,37 +132,19 @@ impl Tasks {
         });
     }
 
-    pub fn filter_terms_regex(&mut self, terms: &[String]) -> Result {
-        let regset = RegexSetBuilder::new(terms).case_insensitive(true).build()?;
-        self.0.retain(|t| regset.is_match(t.raw.as_str()));
-        Ok(())
+    /// Keep only tasks matching compiled filter expression
+    pub fn filter(&mut self, filter: &Filter) {
+        self.0.retain(|t| filter.matches(t));
     }
 
-    /// Sort task list by slice of TaskSort objects
-    pub fn sort(&mut self, sorts: &[SortBy]) {
+    /// Sort task list by keys in order; later keys break ties of earlier
+    /// ones. Tasks without an optional value are placed by `missing`.
+    pub fn sort(&mut self, keys: &[SortKey], missing: Missing) {
         self.0.sort_by(|a, b| {
-            let mut cmp = Ordering::Equal;
-            for sort_field in sorts {
-                if cmp != Ordering::Equal {
-                    break;
-                }
-                cmp = match sort_field {
-                    SortBy::CompleteDate => a.parsed.finish_date.cmp(&b.parsed.finish_date),
-                    SortBy::Completed => a.parsed.finished.cmp(&b.parsed.finished),
-                    SortBy::Context => a.parsed.contexts.get(0).cmp(&b.parsed.contexts.get(0)),
-                    SortBy::CreateDate => a.parsed.create_date.cmp(&b.parsed.create_date),
-                    SortBy::DueDate => a.parsed.due_date.cmp(&b.parsed.due_date),
-                    SortBy::Id => a.id.cmp(&b.id),
-                    SortBy::Priority => a.parsed.priority.cmp(&b.parsed.priority),
-                    SortBy::Project => a.parsed.projects.get(0).cmp(&b.parsed.projects.get(0)),
-                    SortBy::Body => a.parsed.subject.cmp(&b.parsed.subject),
-                    SortBy::Raw => a.raw.cmp(&b.raw),
-                    SortBy::ThresholdDate => {
-                        a.parsed.threshold_date.cmp(&b.parsed.threshold_date)
-                    }
-                };
-            }
-            cmp
+            keys.iter()
+                .map(|key| key.compare(a, b, missing))
+                .find(|cmp| cmp.is_ne())
+                .unwrap_or(Ordering::Equal)
         })
     }
 }
@@ -188,7 +181,12 @@ impl Task {
 
     /// Returns true if the task is a blank line
     pub fn is_blank(&self) -> bool {
-        self.raw == ""
+        self.raw.is_empty()
+    }
+
+    /// Returns true if the task is open and its threshold date is after `today`
+    pub fn is_deferred(&self, today: NaiveDate) -> bool {
+        !self.parsed.finished && self.parsed.threshold_date.is_some_and(|d| d > today)
     }
 
     /// Normalize whitespace (condense >1 space to 1) and reparse
@@ -199,6 +197,32 @@ impl Task {
         )
     }
 
+    /// Priority letter if the raw line starts with a `(X) ` prefix
+    pub fn priority_prefix(&self) -> Option<char> {
+        let bytes = self.raw.as_bytes();
+        if bytes.len() >= 4
+            && bytes[0] == b'('
+            && bytes[1].is_ascii_uppercase()
+            && bytes[2] == b')'
+            && bytes[3] == b' '
+        {
+            return Some(bytes[1] as char);
+        }
+        None
+    }
+
+    /// Replace, add or (if `None`) remove the priority prefix and reparse
+    pub fn with_priority(&self, priority: Option<char>) -> Self {
+        let text = match self.priority_prefix() {
+            Some(_) => &self.raw[4..],
+            None => self.raw.as_str(),
+        };
+        match priority {
+            Some(p) => Task::new(self.id, &format!("({}) {}", p, text)),
+            None => Task::new(self.id, text),
+        }
+    }
+
     /// Turn into plain string with properly padded line number
     #[allow(dead_code)]
     pub fn stringify(&self, task_ct: usize) -> impl Display {
@@ -236,7 +260,7 @@ pub fn tasks_to_string(ctx: &mut AppContext) -> Result<String> {
     if ctx.opts.remove_blank_lines {
         ctx.tasks.retain(|t| !t.is_blank());
     }
-    Ok(ctx.tasks.to_string())
+    Ok(ctx.tasks.to_raw_string())
 }
 
 /// Fields of `Task` we can sort by
@@ -248,24 +272,141 @@ pub enum SortBy {
     CompleteDate,
     /// Whether task is completed or not
     Completed,
-    /// The first context
+    /// The contexts, in order
     Context,
     /// Create date if present
+    #[clap(alias = "created")]
     CreateDate,
     /// Due date tag if present
+    #[clap(alias = "due")]
     DueDate,
     /// Line number
     Id,
     /// Priority code (A-Z)
+    #[clap(alias = "pri")]
     Priority,
-    /// The first project
+    /// The projects, in order
     Project,
     /// The unparsed line from todo.txt file
     Raw,
     /// Threshold date tag if present
+    #[clap(alias = "t", alias = "threshold")]
     ThresholdDate,
 }
 
+/// Where tasks without a value of a sort key are placed
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Missing {
+    First,
+    Last,
+}
+
+/// Field and direction to sort tasks by, e.g. `due:desc`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SortKey {
+    pub field:      SortBy,
+    pub descending: bool,
+}
+
+impl SortKey {
+    /// Ascending key for field
+    pub fn asc(field: SortBy) -> Self {
+        SortKey {
+            field,
+            descending: false,
+        }
+    }
+
+    /// Compare tasks by this key. Dates, priorities, projects and contexts
+    /// are optional: tasks without them are placed by `missing` whatever the
+    /// direction. Priority A comes before B.
+    pub fn compare(&self, a: &Task, b: &Task, missing: Missing) -> Ordering {
+        let (pa, pb) = (&a.parsed, &b.parsed);
+        let pri = |p: &todo_txt::Task| {
+            (!p.priority.is_lowest()).then(|| u8::from(p.priority.clone()))
+        };
+        let list = |v: &[String]| (!v.is_empty()).then(|| v.to_vec());
+        let opt = |a, b| cmp_optional(a, b, self.descending, missing);
+        let cmp = match self.field {
+            SortBy::Body => pa.subject.cmp(&pb.subject),
+            SortBy::Completed => pa.finished.cmp(&pb.finished),
+            SortBy::Id => a.id.cmp(&b.id),
+            SortBy::Raw => a.raw.cmp(&b.raw),
+            SortBy::CompleteDate => return opt(pa.finish_date, pb.finish_date),
+            SortBy::CreateDate => return opt(pa.create_date, pb.create_date),
+            SortBy::DueDate => return opt(pa.due_date, pb.due_date),
+            SortBy::ThresholdDate => return opt(pa.threshold_date, pb.threshold_date),
+            SortBy::Priority => {
+                return cmp_optional(pri(pa), pri(pb), self.descending, missing)
+            }
+            SortBy::Context => {
+                return cmp_optional(
+                    list(&pa.contexts),
+                    list(&pb.contexts),
+                    self.descending,
+                    missing,
+                )
+            }
+            SortBy::Project => {
+                return cmp_optional(
+                    list(&pa.projects),
+                    list(&pb.projects),
+                    self.descending,
+                    missing,
+                )
+            }
+        };
+        if self.descending {
+            cmp.reverse()
+        } else {
+            cmp
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    /// Parse `field` or `field:asc` or `field:desc`
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, dir) = match s.split_once(':') {
+            Some((name, dir)) => (name, Some(dir)),
+            None => (s, None),
+        };
+        let field = <SortBy as clap::ArgEnum>::from_str(name.trim(), true)?;
+        let descending = match dir.map(|d| d.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(d) => return Err(format!("invalid sort direction: {} (use asc or desc)", d)),
+        };
+        Ok(SortKey { field, descending })
+    }
+}
+
+/// Parse comma-separated sort keys, e.g. `due:desc,priority,project`
+pub fn parse_sort_keys(s: &str) -> Result<Vec<SortKey>> {
+    s.split(',')
+        .map(|k| k.parse().map_err(|e| format_err!("invalid sort key '{}': {}", k, e)))
+        .collect()
+}
+
+/// Compare optional values in `descending` or ascending order, with `None`
+/// placed by `missing`
+fn cmp_optional<T>(a: Option<T>, b: Option<T>, descending: bool, missing: Missing) -> Ordering
+where
+    T: Ord,
+{
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) if missing == Missing::First => Ordering::Less,
+        (Some(_), None) if missing == Missing::Last => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
@@ -277,6 +418,7 @@ mod tests {
     const SUBJECT: &str = "Get new +pricing for +item @work";
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     /// Test todo_txt library string -> task
     fn str_to_task() {
         let task = Task::from_str(STR_TASK).unwrap();
@@ -311,4 +453,54 @@ mod tests {
         };
         assert_eq!(task, expect);
     }
+
+    #[test]
+    /// Sort by several keys with direction and placement of missing values
+    fn sort_multi_key() -> crate::prelude::Result {
+        use super::{parse_sort_keys, Missing, SortBy, SortKey, Tasks};
+        let mut tasks = Tasks(vec![
+            super::Task::new(1, "Write +b +x"),
+            super::Task::new(2, "(B) Call +b +a due:2026-10-20"),
+            super::Task::new(3, "(A) Pay due:2026-10-20"),
+            super::Task::new(4, "Read +b +a due:2026-11-01"),
+        ]);
+        let keys = parse_sort_keys("due:desc, pri")?;
+        assert_eq!(keys, vec![
+            SortKey {
+                field:      SortBy::DueDate,
+                descending: true,
+            },
+            SortKey::asc(SortBy::Priority),
+        ]);
+        let ids = |tasks: &Tasks| tasks.iter().map(|t| t.id).collect::<Vec<usize>>();
+        tasks.sort(&keys, Missing::Last);
+        assert_eq!(ids(&tasks), vec![4, 3, 2, 1]);
+        tasks.sort(&keys, Missing::First);
+        assert_eq!(ids(&tasks), vec![1, 4, 3, 2]);
+        // projects are compared beyond the first one
+        tasks.sort(&parse_sort_keys("project,id:desc")?, Missing::Last);
+        assert_eq!(ids(&tasks), vec![4, 2, 1, 3]);
+        assert!(parse_sort_keys("due:sideways").is_err());
+        Ok(())
+    }
+
+    #[test]
+    /// Add, replace and remove the priority prefix of a raw line
+    fn task_with_priority() {
+        let task = super::Task::new(1, "(B) 2019-12-18 Get new +pricing");
+        assert_eq!(task.priority_prefix(), Some('B'));
+        let task = task.with_priority(Some('A'));
+        assert_eq!(task.raw, "(A) 2019-12-18 Get new +pricing");
+        assert_eq!(task.parsed.priority, 'A');
+        let task = task.with_priority(None);
+        assert_eq!(task.raw, "2019-12-18 Get new +pricing");
+        assert!(task.parsed.priority.is_lowest());
+        assert_eq!(task.priority_prefix(), None);
+        // only uppercase letters are priorities
+        for raw in &["(a) lowercase", "(1) call mom", "(B)no space"] {
+            let task = super::Task::new(1, *raw);
+            assert_eq!(task.priority_prefix(), None, "{}", raw);
+            assert_eq!(task.with_priority(Some('C')).raw, format!("(C) {}", raw));
+        }
+    }
 }
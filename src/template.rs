@@ -0,0 +1,298 @@
+//! Templates for lines of task listings, e.g. `{id:>3} {pri} {subject} {due:%b %d}`
+use crate::{actions::tags::TagKind, prelude::*, task::Task};
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+/// Template that reproduces the todo.sh layout
+pub const DEFAULT_TEMPLATE: &str = "{id} {raw}";
+
+/// Default format of date placeholders
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Task field that can be used as a placeholder
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Field {
+    /// Line number, zero-padded to the width of the highest line number
+    Id,
+    /// Unparsed line
+    Raw,
+    /// `x` if the task is done
+    Done,
+    /// Priority as `(A)`
+    Pri,
+    /// Text without priority, dates and `key:value` tags
+    Subject,
+    /// Projects as written in the task
+    Projects,
+    /// Contexts as written in the task
+    Contexts,
+    /// `key:value` tags other than due and t
+    Tags,
+    Due,
+    Threshold,
+    Created,
+    Completed,
+}
+
+impl Field {
+    /// Field for placeholder name
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "id" => Self::Id,
+            "raw" => Self::Raw,
+            "done" | "x" => Self::Done,
+            "pri" | "priority" => Self::Pri,
+            "subject" | "body" => Self::Subject,
+            "projects" => Self::Projects,
+            "contexts" => Self::Contexts,
+            "tags" => Self::Tags,
+            "due" => Self::Due,
+            "t" | "threshold" => Self::Threshold,
+            "created" => Self::Created,
+            "completed" => Self::Completed,
+            _ => return None,
+        })
+    }
+
+    fn is_date(self) -> bool {
+        matches!(
+            self,
+            Self::Due | Self::Threshold | Self::Created | Self::Completed
+        )
+    }
+
+    /// True if the field is written word by word so projects and contexts
+    /// get their own colors
+    pub fn has_tags(self) -> bool {
+        matches!(
+            self,
+            Self::Raw | Self::Subject | Self::Projects | Self::Contexts
+        )
+    }
+}
+
+/// Alignment of a padded placeholder
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Part of a parsed template
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Part {
+    Literal(String),
+    Placeholder {
+        field: Field,
+        /// Alignment, width and whether to pad with zeros
+        pad:   Option<(Align, usize, bool)>,
+        /// strftime format of date fields
+        date:  Option<String>,
+    },
+}
+
+/// Parsed list line template
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Template {
+    pub parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse template. Placeholders are `{field}` or `{field:spec}`, where spec
+    /// is a strftime format for dates and `[<>^][0]width` otherwise. Literal
+    /// braces are written as `{{` and `}}`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => bail!("unclosed placeholder in list format: {:?}", s),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_placeholder(&inner)?);
+                }
+                '}' => bail!("unmatched '}}' in list format: {:?}", s),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+}
+
+/// Parse `field` or `field:spec`
+fn parse_placeholder(s: &str) -> Result<Part> {
+    let (name, spec) = match s.split_once(':') {
+        Some((name, spec)) => (name, Some(spec)),
+        None => (s, None),
+    };
+    let field = Field::from_name(name.trim())
+        .ok_or_else(|| format_err!("unknown field in list format: {{{}}}", s))?;
+    let (mut pad, mut date) = (None, None);
+    match spec {
+        Some(spec) if field.is_date() => {
+            // formats with time fields such as %H parse but fail on dates
+            let sample = NaiveDate::from_ymd(2000, 1, 1);
+            if write!(String::new(), "{}", sample.format(spec)).is_err() {
+                bail!("invalid date format in list format: {{{}}}", s);
+            }
+            date = Some(spec.to_string());
+        }
+        Some(spec) => {
+            let (align, rest) = match spec.chars().next() {
+                Some('<') => (Align::Left, &spec[1..]),
+                Some('>') => (Align::Right, &spec[1..]),
+                Some('^') => (Align::Center, &spec[1..]),
+                // numbers are right-aligned by default
+                _ if field == Field::Id => (Align::Right, spec),
+                _ => (Align::Left, spec),
+            };
+            let zero = rest.starts_with('0');
+            let width = rest
+                .parse()
+                .map_err(|_| format_err!("invalid width in list format: {{{}}}", s))?;
+            pad = Some((align, width, zero));
+        }
+        None => {}
+    }
+    Ok(Part::Placeholder { field, pad, date })
+}
+
+/// Words of a field written word by word. Projects and contexts hidden with
+/// `-+` or `-@` are left out.
+pub fn field_words(
+    task: &Task,
+    field: Field,
+    hide_project: bool,
+    hide_context: bool,
+) -> Vec<String> {
+    let text = match field {
+        Field::Raw => task.raw.clone(),
+        Field::Subject => task.parsed.subject.clone(),
+        Field::Projects => TagKind::Project.words(task).join(" "),
+        Field::Contexts => TagKind::Context.words(task).join(" "),
+        _ => return vec![field_text(task, field, &None, 0)],
+    };
+    text.split_whitespace()
+        .filter(|w| !(hide_project && w.starts_with('+') || hide_context && w.starts_with('@')))
+        .map(String::from)
+        .collect()
+}
+
+/// Plain text of a field, before padding. `task_ct` is the number of lines,
+/// used to pad `{id}` with zeros; pass 0 for no padding.
+pub fn field_text(task: &Task, field: Field, date: &Option<String>, task_ct: usize) -> String {
+    let p = &task.parsed;
+    let date = |d: Option<NaiveDate>| {
+        d.map(|d| d.format(date.as_deref().unwrap_or(DATE_FORMAT)).to_string())
+            .unwrap_or_default()
+    };
+    match field {
+        Field::Id => format!("{:0width$}", task.id, width = task_ct.to_string().len()),
+        Field::Done if p.finished => "x".into(),
+        Field::Done => String::new(),
+        Field::Pri if p.priority.is_lowest() => String::new(),
+        Field::Pri => format!("({})", char::from(p.priority.clone())),
+        Field::Tags => p
+            .tags
+            .iter()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect::<Vec<String>>()
+            .join(" "),
+        Field::Due => date(p.due_date),
+        Field::Threshold => date(p.threshold_date),
+        Field::Created => date(p.create_date),
+        Field::Completed => date(p.finish_date),
+        Field::Raw | Field::Subject | Field::Projects | Field::Contexts => {
+            field_words(task, field, false, false).join(" ")
+        }
+    }
+}
+
+/// Spaces before and after text of `len` chars to fill `pad`
+pub fn padding(len: usize, pad: Option<(Align, usize, bool)>) -> (usize, usize) {
+    match pad {
+        Some((align, width, _)) if width > len => {
+            let fill = width - len;
+            match align {
+                Align::Left => (0, fill),
+                Align::Right => (fill, 0),
+                Align::Center => (fill / 2, fill - fill / 2),
+            }
+        }
+        _ => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    /// Placeholders with and without specs are parsed between literals
+    fn template_parse() -> Result {
+        let t = Template::parse("{id:>3} {{{pri}}} {due:%b %d}")?;
+        assert_eq!(t.parts, vec![
+            Part::Placeholder {
+                field: Field::Id,
+                pad:   Some((Align::Right, 3, false)),
+                date:  None,
+            },
+            Part::Literal(" {".into()),
+            Part::Placeholder {
+                field: Field::Pri,
+                pad:   None,
+                date:  None,
+            },
+            Part::Literal("} ".into()),
+            Part::Placeholder {
+                field: Field::Due,
+                pad:   None,
+                date:  Some("%b %d".into()),
+            },
+        ]);
+        assert!(Template::parse("{nope}").is_err());
+        assert!(Template::parse("{id").is_err());
+        assert!(Template::parse("{subject:wide}").is_err());
+        assert!(Template::parse("{due:%Q}").is_err());
+        assert!(Template::parse("{due:%H:%M}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    /// Field text is taken from the parsed task
+    fn template_field_text() {
+        let task = Task::new(7, "(B) 2026-10-01 Call +Mom @phone due:2026-10-20 est:1h");
+        let due = Some("%b %d".to_string());
+        assert_eq!(field_text(&task, Field::Id, &None, 12), "07");
+        assert_eq!(field_text(&task, Field::Pri, &None, 12), "(B)");
+        assert_eq!(field_text(&task, Field::Subject, &None, 12), "Call +Mom @phone");
+        assert_eq!(field_text(&task, Field::Projects, &None, 12), "+Mom");
+        assert_eq!(field_text(&task, Field::Tags, &None, 12), "est:1h");
+        assert_eq!(field_text(&task, Field::Due, &due, 12), "Oct 20");
+        assert_eq!(field_text(&task, Field::Done, &None, 12), "");
+        assert_eq!(padding(3, Some((Align::Center, 8, false))), (2, 3));
+    }
+}
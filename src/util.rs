@@ -28,3 +28,13 @@ pub fn get_pri_name(pri: u8) -> Option<String> {
         _ => None,
     }
 }
+
+/// Create empty scratch directory for a unit test. `name` must be unique
+/// among tests, e.g. `backup-prune`.
+#[cfg(test)]
+pub(crate) fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("todors-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
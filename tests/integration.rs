@@ -1,6 +1,11 @@
 use duct::cmd;
 use pretty_assertions::assert_eq;
-use todors::prelude::*;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use todors::{file::FileLock, prelude::*};
 
 const BIN: &str = env!("CARGO_BIN_EXE_todors");
 const CFG: &str = "tests/todo.toml";
@@ -66,3 +71,542 @@ fn compare_bin_lsa_plain() -> Result {
     assert_eq!(todo_sh, todors);
     Ok(())
 }
+
+/// Copy the test files into a scratch directory so commands that modify
+/// todo.txt and done.txt don't clobber the shared fixtures. Calling again with
+/// the same name resets the directory to its original state.
+fn fixture_dir(name: &str) -> Result<PathBuf> {
+    let dir = env::temp_dir().join(format!("todors-{}", name));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    for file in &["todo.txt", "done.txt", "report.txt"] {
+        fs::copy(Path::new("tests").join(file), dir.join(file))?;
+    }
+    let dir_str = dir.to_string_lossy();
+    fs::write(
+        dir.join("todo.toml"),
+        fs::read_to_string(CFG)?.replace("$HOME/git/todors/tests", &dir_str),
+    )?;
+    fs::write(
+        dir.join("todo.cfg"),
+        fs::read_to_string(TODO_CFG)?.replace("$HOME/git/todors/tests", &dir_str),
+    )?;
+    Ok(dir)
+}
+
+/// Contents of todo.txt and done.txt in directory
+fn read_files(dir: &Path) -> Result<(String, String)> {
+    Ok((
+        fs::read_to_string(dir.join("todo.txt"))?,
+        fs::read_to_string(dir.join("done.txt"))?,
+    ))
+}
+
+/// Run the same command with both todors and todo.sh against fresh copies of
+/// the fixtures and compare the resulting files. Returns the output of todo.sh
+/// and todors.
+fn run_mutation(name: &str, args: &[&str]) -> Result<(String, String)> {
+    let dir = fixture_dir(name)?;
+    let todo_sh = cmd(TODO_BIN, args)
+        .env("TODOTXT_CFG_FILE", dir.join("todo.cfg"))
+        .env("TODOTXT_SORT_COMMAND", "sort")
+        .read()?;
+    let todo_sh_files = read_files(&dir)?;
+    let dir = fixture_dir(name)?;
+    let todors = cmd(BIN, args)
+        .env("TODORS_CFG_FILE", dir.join("todo.toml"))
+        .read()?;
+    let todors_files = read_files(&dir)?;
+    assert_eq!(todo_sh_files, todors_files);
+    Ok((todo_sh, todors))
+}
+
+/// Compare output and resulting files of a command that changes files
+fn compare_mutation(name: &str, args: &[&str]) -> Result {
+    let (todo_sh, todors) = run_mutation(name, args)?;
+    assert_eq!(todo_sh, todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `archive` command
+fn compare_bin_archive() -> Result {
+    compare_mutation("archive", &["archive"])
+}
+
+#[test]
+/// Compare `pri` command
+fn compare_bin_pri() -> Result {
+    compare_mutation("pri", &["pri", "3", "b"])
+}
+
+#[test]
+/// Compare `depri` command
+fn compare_bin_depri() -> Result {
+    compare_mutation("depri", &["depri", "1", "2"])
+}
+
+#[test]
+/// Compare `do` command
+fn compare_bin_do() -> Result {
+    compare_mutation("do", &["do", "1,3"])
+}
+
+#[test]
+/// Compare `append` command. todors also prints the line before the change,
+/// todo.sh only the line after it.
+fn compare_bin_append() -> Result {
+    let (todo_sh, todors) = run_mutation("append", &["append", "2", "; bring boxes"])?;
+    assert!(todors.ends_with(&todo_sh), "{}", todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `prepend` command, which keeps the priority and creation date first
+fn compare_bin_prepend() -> Result {
+    let (todo_sh, todors) = run_mutation("prepend", &["prepend", "6", "Really"])?;
+    assert!(todors.ends_with(&todo_sh), "{}", todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `replace` command
+fn compare_bin_replace() -> Result {
+    compare_mutation("replace", &["replace", "6", "Call work"])
+}
+
+#[test]
+/// Compare `deduplicate` command
+fn compare_bin_deduplicate() -> Result {
+    compare_mutation("deduplicate", &["deduplicate"])
+}
+
+#[test]
+/// Compare `lsp` command
+fn compare_bin_lsp() -> Result {
+    let todo_sh = cmd!(TODO_BIN, "-p", "lsp")
+        .env("TODOTXT_CFG_FILE", TODO_CFG)
+        .env("TODOTXT_SORT_COMMAND", "sort")
+        .read()?;
+    let todors = cmd!(BIN, "-p", "lsp").env("TODORS_CFG_FILE", CFG).read()?;
+    assert_eq!(todo_sh, todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `lsp` command with priority range and term
+fn compare_bin_lsp_range() -> Result {
+    let todo_sh = cmd!(TODO_BIN, "-p", "lsp", "A-C", "phone")
+        .env("TODOTXT_CFG_FILE", TODO_CFG)
+        .env("TODOTXT_SORT_COMMAND", "sort")
+        .read()?;
+    let todors = cmd!(BIN, "-p", "lsp", "A-C", "phone")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    assert_eq!(todo_sh, todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `lsprj` command
+fn compare_bin_lsprj() -> Result {
+    let todo_sh = cmd!(TODO_BIN, "lsprj")
+        .env("TODOTXT_CFG_FILE", TODO_CFG)
+        .env("LC_ALL", "C")
+        .read()?;
+    let todors = cmd!(BIN, "lsprj").env("TODORS_CFG_FILE", CFG).read()?;
+    assert_eq!(todo_sh, todors);
+    Ok(())
+}
+
+#[test]
+/// Compare `lsc` command
+fn compare_bin_lsc() -> Result {
+    let todo_sh = cmd!(TODO_BIN, "lsc")
+        .env("TODOTXT_CFG_FILE", TODO_CFG)
+        .env("LC_ALL", "C")
+        .read()?;
+    let todors = cmd!(BIN, "lsc").env("TODORS_CFG_FILE", CFG).read()?;
+    assert_eq!(todo_sh, todors);
+    Ok(())
+}
+
+#[test]
+/// Moving a task to the file it is in fails without changing the file
+fn mv_same_file() -> Result {
+    let dir = fixture_dir("mv_same_file")?;
+    let (todo, _) = read_files(&dir)?;
+    for dest in &["todo.txt", "./todo.txt"] {
+        let output = cmd!(BIN, "-f", "mv", "1", dest)
+            .env("TODORS_CFG_FILE", dir.join("todo.toml"))
+            .stderr_capture()
+            .unchecked()
+            .run()?;
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("same file"), "{}", stderr);
+        assert_eq!(todo, read_files(&dir)?.0);
+    }
+    Ok(())
+}
+
+#[test]
+/// `addto` only writes to existing files inside the todo.txt directory
+fn addto_stays_in_todo_dir() -> Result {
+    let dir = fixture_dir("addto_stays_in_todo_dir")?;
+    let outside = env::temp_dir().join("todors-addto-outside.txt");
+    fs::write(&outside, "")?;
+    let mut dests = vec![
+        "../todors-addto-outside.txt".to_string(),
+        outside.to_string_lossy().into_owned(),
+        "missing.txt".to_string(),
+    ];
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&outside, dir.join("link.txt"))?;
+        dests.push("link.txt".to_string());
+    }
+    for dest in &dests {
+        let output = cmd!(BIN, "addto", dest, "Escape")
+            .env("TODORS_CFG_FILE", dir.join("todo.toml"))
+            .stdout_capture()
+            .stderr_capture()
+            .unchecked()
+            .run()?;
+        assert!(!output.status.success(), "{}", dest);
+        assert_eq!(fs::read_to_string(&outside)?, "", "{}", dest);
+    }
+    assert!(!dir.join("missing.txt").exists());
+    fs::remove_file(&outside)?;
+    Ok(())
+}
+
+#[test]
+/// `lf` lists a file in the todo.txt directory with a footer named after it
+fn listfile_someday() -> Result {
+    let dir = fixture_dir("listfile_someday")?;
+    fs::write(dir.join("someday.txt"), "Learn to juggle\n(A) Write a novel +book\n")?;
+    let cfg = dir.join("todo.toml");
+    let out = cmd!(BIN, "-p", "lf", "someday.txt")
+        .env("TODORS_CFG_FILE", &cfg)
+        .read()?;
+    assert_eq!(
+        out,
+        "2 (A) Write a novel +book\n1 Learn to juggle\n--\nSOMEDAY: 2 of 2 tasks shown"
+    );
+    let out = cmd!(BIN, "-p", "lf", "someday.txt", "juggle")
+        .env("TODORS_CFG_FILE", &cfg)
+        .read()?;
+    assert_eq!(out, "1 Learn to juggle\n--\nSOMEDAY: 1 of 2 tasks shown");
+    let out = cmd!(BIN, "lf").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert!(out.contains("\nsomeday.txt\n"), "{}", out);
+    Ok(())
+}
+
+#[test]
+/// `report` counts tasks, not the blank lines that preserve line numbers
+fn report_skips_blank_lines() -> Result {
+    let dir = fixture_dir("report_skips_blank_lines")?;
+    let out = cmd!(BIN, "report")
+        .env("TODORS_CFG_FILE", dir.join("todo.toml"))
+        .read()?;
+    assert!(out.ends_with(" 7 4\nTODO: Report file updated."), "{}", out);
+    Ok(())
+}
+
+#[test]
+/// Concurrent `add` commands must not lose any tasks
+fn concurrent_add() -> Result {
+    let dir = fixture_dir("concurrent_add")?;
+    let before = fs::read_to_string(dir.join("todo.txt"))?.lines().count();
+    let handles = (0..8)
+        .map(|i| {
+            cmd!(BIN, "add", format!("Concurrent task {}", i))
+                .env("TODORS_CFG_FILE", dir.join("todo.toml"))
+                .stdout_null()
+                .start()
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    for handle in handles {
+        handle.wait()?;
+    }
+    let todo = fs::read_to_string(dir.join("todo.txt"))?;
+    assert_eq!(todo.lines().count(), before + 8);
+    for i in 0..8 {
+        assert!(todo.contains(&format!("Concurrent task {}\n", i)));
+    }
+    Ok(())
+}
+
+#[test]
+/// A held lock makes changing commands fail after the timeout, while reading
+/// commands and a lock file left behind by a dead process don't block
+fn lock_timeout() -> Result {
+    let dir = fixture_dir("lock_timeout")?;
+    let cfg = dir.join("todo.toml");
+    fs::write(
+        &cfg,
+        fs::read_to_string(&cfg)?.replace("lock_timeout = 10", "lock_timeout = 0"),
+    )?;
+    let lock = FileLock::acquire(dir.join("todo.txt"), Duration::from_secs(0))?;
+    let output = cmd!(BIN, "add", "Blocked")
+        .env("TODORS_CFG_FILE", &cfg)
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("todo.txt.lock"), "{}", stderr);
+    cmd!(BIN, "ls").env("TODORS_CFG_FILE", &cfg).read()?;
+    drop(lock);
+    assert!(dir.join("todo.txt.lock").exists());
+    cmd!(BIN, "add", "Not blocked").env("TODORS_CFG_FILE", &cfg).read()?;
+    Ok(())
+}
+
+#[test]
+/// Undo reverts several mutating commands and redo reapplies them
+fn undo_redo() -> Result {
+    let dir = fixture_dir("undo_redo")?;
+    let cfg = dir.join("todo.toml");
+    let original = read_files(&dir)?;
+    cmd!(BIN, "pri", "3", "A").env("TODORS_CFG_FILE", &cfg).read()?;
+    cmd!(BIN, "do", "1").env("TODORS_CFG_FILE", &cfg).read()?;
+    let changed = read_files(&dir)?;
+    cmd!(BIN, "ls").env("TODORS_CFG_FILE", &cfg).read()?;
+    let list = cmd!(BIN, "undo", "--list").env("TODORS_CFG_FILE", &cfg).read()?;
+    let commands = list
+        .lines()
+        .take_while(|l| *l != "--")
+        .filter(|l| !l.starts_with("          "))
+        .map(|l| l.rsplit("  ").next().unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(commands, vec!["do 1", "pri 3 A"]);
+    cmd!(BIN, "undo", "2").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert_eq!(original, read_files(&dir)?);
+    cmd!(BIN, "redo", "2").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert_eq!(changed, read_files(&dir)?);
+    Ok(())
+}
+
+#[test]
+/// Tasks with a future threshold date are hidden and counted in the footer
+/// unless `--all-thresholds` is given
+fn list_hides_deferred() -> Result {
+    let dir = fixture_dir("list_hides_deferred")?;
+    let cfg = dir.join("todo.toml");
+    fs::write(
+        &cfg,
+        fs::read_to_string(&cfg)?
+            .replace("hide_future_threshold = false", "hide_future_threshold = true"),
+    )?;
+    let deferred = "08 Learn how to add 2+2 @someday t:2099-12-31";
+    let out = cmd!(BIN, "-p", "ls").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert!(!out.contains(deferred), "{}", out);
+    assert!(out.ends_with(
+        "--\nTODO: 7 of 8 tasks shown\n\
+         TODO: 1 deferred task(s) hidden (use --all-thresholds to show)"
+    ));
+    let out = cmd!(BIN, "-p", "--all-thresholds", "ls")
+        .env("TODORS_CFG_FILE", &cfg)
+        .read()?;
+    assert!(out.contains(deferred), "{}", out);
+    assert!(out.ends_with("--\nTODO: 8 of 8 tasks shown"), "{}", out);
+    Ok(())
+}
+
+#[test]
+/// `ls --format jsonl` prints one task per line and the footer counts
+fn list_jsonl() -> Result {
+    let out = cmd!(BIN, "ls", "--format", "jsonl", "@phone")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    let lines = out
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<serde_json::Result<Vec<serde_json::Value>>>()?;
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0]["id"], 1);
+    assert_eq!(lines[0]["priority"], "A");
+    assert_eq!(lines[0]["contexts"], serde_json::json!(["phone"]));
+    assert_eq!(lines[2]["create_date"], "2019-01-01");
+    assert_eq!(lines[3]["finished"], true);
+    let summary = &lines[4]["summary"];
+    assert_eq!(summary["shown"], 4);
+    assert_eq!(summary["total"], 8);
+    Ok(())
+}
+
+#[test]
+/// `lsprj --format json` lists the same projects as text output
+fn listproj_json() -> Result {
+    let text = cmd!(BIN, "-p", "lsprj").env("TODORS_CFG_FILE", CFG).read()?;
+    let json = cmd!(BIN, "lsprj", "--format", "json")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    let json: serde_json::Value = serde_json::from_str(&json)?;
+    let tags = json["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["tag"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(text.lines().collect::<Vec<&str>>(), tags);
+    assert_eq!(json["tags"][0]["open"], 2);
+    Ok(())
+}
+
+#[test]
+/// Terms starting with a minus exclude tasks in `lsprj` and `export` while
+/// their flags still work, and options after the terms of `ls` are rejected
+fn hyphen_terms() -> Result {
+    let out = cmd!(BIN, "-p", "lsprj", "-@phone", "-c")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    assert_eq!(out, "+GarageSale  1 open  0 done");
+    let out = cmd!(BIN, "export", "--columns", "id", "-@phone", "-a")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    assert_eq!(out.lines().collect::<Vec<&str>>(), vec![
+        "id", "3", "4", "7", "8", "2", "3"
+    ]);
+    let output = cmd!(BIN, "ls", "a", "--format", "json")
+        .env("TODORS_CFG_FILE", CFG)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"--format\" after TERM"), "{}", stderr);
+    Ok(())
+}
+
+#[test]
+/// Tasks exported as CSV are imported back as the same lines
+fn export_import() -> Result {
+    let dir = fixture_dir("export_import")?;
+    let cfg = dir.join("todo.toml");
+    let csv = dir.join("tasks.csv");
+    let exported = cmd!(BIN, "export", "--columns", "raw")
+        .env("TODORS_CFG_FILE", &cfg)
+        .read()?;
+    fs::write(&csv, &exported)?;
+    let (todo, _) = read_files(&dir)?;
+    cmd!(BIN, "import", &csv, "--dry-run").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert_eq!(todo, read_files(&dir)?.0);
+    cmd!(BIN, "import", &csv).env("TODORS_CFG_FILE", &cfg).read()?;
+    let lines = todo.lines().filter(|l| !l.is_empty()).collect::<Vec<&str>>();
+    assert_eq!(format!("{}{}\n", todo, lines.join("\n")), read_files(&dir)?.0);
+    Ok(())
+}
+
+#[test]
+/// `--format-template` reorders fields and drops spaces of empty ones
+fn list_format_template() -> Result {
+    let out = cmd!(
+        BIN,
+        "-p",
+        "--format-template",
+        "{id:>3} {pri} {subject} {due:%b %d}",
+        "ls",
+        "@phone"
+    )
+    .env("TODORS_CFG_FILE", CFG)
+    .read()?;
+    let expected = "  1 (A) Thank Mom for the meatballs @phone
+  2 (B) Schedule Goodwill pickup +GarageSale @phone
+  6 (D) Call home @phone
+ 10 Call doctor @phone +Health Aug 22
+--
+TODO: 4 of 8 tasks shown";
+    assert_eq!(expected, out);
+    Ok(())
+}
+
+#[test]
+/// `ls --group-by project` shows a styled heading with a count above each
+/// section and a blank line between sections
+fn list_group_by_project() -> Result {
+    let out = cmd!(BIN, "-p", "ls", "--group-by", "project", "@phone")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    let expected = "+GarageSale (1)
+02 (B) Schedule Goodwill pickup +GarageSale @phone
+
++Health (1)
+10 x 2018-08-22 2018-08-20 Call doctor @phone +Health due:2018-08-22 t:2018-08-21
+
+No project (2)
+01 (A) Thank Mom for the meatballs @phone
+06 (D) 2019-01-01 Call home @phone
+--
+TODO: 4 of 8 tasks shown";
+    assert_eq!(expected, out);
+    let out = cmd!(BIN, "ls", "--group-by", "project", "@phone")
+        .env("TODORS_CFG_FILE", CFG)
+        .read()?;
+    // heading style in tests/todo.toml is bold
+    for heading in &["+GarageSale (1)", "+Health (1)", "No project (2)"] {
+        assert!(out.contains(&format!("\x1b[1m{}\x1b[0m\n", heading)), "{}", out);
+    }
+    Ok(())
+}
+
+/// Fixture config with `default_sort` set to `spec`
+fn default_sort_cfg(name: &str, spec: &str) -> Result<PathBuf> {
+    let dir = fixture_dir(name)?;
+    let cfg = dir.join("todo.toml");
+    fs::write(
+        &cfg,
+        fs::read_to_string(&cfg)?.replace(
+            "# default_sort = 'due:desc,priority,project'",
+            &format!("default_sort = '{}'", spec),
+        ),
+    )?;
+    Ok(cfg)
+}
+
+#[test]
+/// The `default_sort` and `sort_missing` settings sort listings unless `-s`
+/// is given
+fn list_default_sort() -> Result {
+    let cfg = default_sort_cfg("list_default_sort", "due:desc,priority")?;
+    let ids = |out: String| -> Vec<String> {
+        out.lines()
+            .take_while(|l| *l != "--")
+            .map(|l| l[..2].to_string())
+            .collect()
+    };
+    let out = cmd!(BIN, "-p", "ls").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert_eq!(ids(out), vec!["07", "10", "01", "02", "06", "03", "04", "08"]);
+    let out = cmd!(BIN, "-p", "-s", "id", "ls")
+        .env("TODORS_CFG_FILE", &cfg)
+        .read()?;
+    assert_eq!(ids(out), vec!["01", "02", "03", "04", "06", "07", "08", "10"]);
+    fs::write(
+        &cfg,
+        fs::read_to_string(&cfg)?.replace("# sort_missing = 'last'", "sort_missing = 'first'"),
+    )?;
+    let out = cmd!(BIN, "-p", "ls").env("TODORS_CFG_FILE", &cfg).read()?;
+    assert_eq!(ids(out), vec!["03", "04", "08", "01", "02", "06", "07", "10"]);
+    Ok(())
+}
+
+#[test]
+/// An invalid `default_sort` setting is reported
+fn list_invalid_default_sort() -> Result {
+    let cfg = default_sort_cfg("list_invalid_default_sort", "bogus")?;
+    let output = cmd!(BIN, "ls")
+        .env("TODORS_CFG_FILE", &cfg)
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("reading default_sort setting"), "{}", stderr);
+    assert!(stderr.contains("bogus"), "{}", stderr);
+    Ok(())
+}